@@ -0,0 +1,144 @@
+//! DS1302 and an SSD1306 display on the same SPI bus.
+//!
+//! The bus lives in a `RefCell`: the DS1302 driver borrows it per transaction through
+//! `DS1302::new_shared`, the display through the small `SharedBus` proxy below. Only one
+//! chip select is active at a time because each driver only asserts its own pin while it holds
+//! the borrow. Mind the polarity: the DS1302 CE is active high and idles low, the SSD1306 CS is
+//! active low and idles high.
+//!
+//! The display needs the bus MSB first, so the DS1302 bytes are reversed in software with
+//! `BitOrder::MsbFirst`.
+
+#![no_main]
+#![no_std]
+
+use core::cell::RefCell;
+use core::fmt::Write;
+use cortex_m_rt::entry;
+use panic_probe as _;
+
+use display_interface_spi::SPIInterface;
+use ssd1306::{prelude::*, Builder};
+
+use stm32f1xx_hal::{
+    delay::Delay as HAL_DELAY,
+    prelude::*,
+    spi::{Mode, Phase, Polarity, Spi},
+    stm32,
+};
+
+use ds1302::{BitOrder, Delay, Mode as ds1302_mode, DS1302};
+
+use embedded_graphics::{
+    fonts::{Font6x12, Text},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    style::TextStyle,
+};
+use embedded_hal::blocking::spi;
+use heapless::consts::*;
+use heapless::String;
+
+struct NoWait;
+
+impl<const TIMER_HZ: u32> Delay<TIMER_HZ> for NoWait {
+    type Error = core::convert::Infallible;
+
+    fn now(&mut self) -> fugit::TimerInstantU32<TIMER_HZ> {
+        fugit::TimerInstantU32::from_ticks(0)
+    }
+
+    fn start(&mut self, _duration: fugit::TimerDurationU32<TIMER_HZ>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+// Borrows the shared bus for each write of the display
+struct SharedBus<'a, SPI>(&'a RefCell<SPI>);
+
+impl<SPI: spi::Write<u8>> spi::Write<u8> for SharedBus<'_, SPI> {
+    type Error = SPI::Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        self.0.borrow_mut().write(words)
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let dp = stm32::Peripherals::take().unwrap();
+    let cp = cortex_m::peripheral::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let mut rcc = dp.RCC.constrain();
+
+    let clocks = rcc
+        .cfgr
+        .sysclk(16.mhz())
+        .pclk1(8.mhz())
+        .freeze(&mut flash.acr);
+
+    let mut afio = dp.AFIO.constrain();
+    let mut delay = HAL_DELAY::new(cp.SYST, clocks);
+
+    let mut gpioa = dp.GPIOA.split();
+    let sck = gpioa.pa5.into_alternate_push_pull(&mut gpioa.crl);
+    let miso = gpioa.pa6.into_floating_input(&mut gpioa.crl);
+    let mosi = gpioa.pa7.into_alternate_push_pull(&mut gpioa.crl);
+    //ds1302 CE, idle low
+    let mut ce = gpioa.pa4.into_push_pull_output(&mut gpioa.crl);
+    ce.set_low().unwrap();
+
+    //ssd1306 CS, idle high, data/command and reset
+    let mut gpiob = dp.GPIOB.split();
+    let mut disp_cs = gpiob.pb0.into_push_pull_output(&mut gpiob.crl);
+    disp_cs.set_high().unwrap();
+    let dc = gpiob.pb1.into_push_pull_output(&mut gpiob.crl);
+    let mut rst = gpiob.pb10.into_push_pull_output(&mut gpiob.crh);
+
+    let spi_mode = Mode {
+        polarity: Polarity::IdleLow,
+        phase: Phase::CaptureOnFirstTransition,
+    };
+    // 500 kHz is the DS1302 maximum at 2 V
+    let spi = Spi::spi1(
+        dp.SPI1,
+        (sck, miso, mosi),
+        &mut afio.mapr,
+        spi_mode,
+        500.khz(),
+        clocks,
+    );
+    let bus = RefCell::new(spi);
+
+    let interface = SPIInterface::new(SharedBus(&bus), dc, disp_cs);
+    let mut disp: GraphicsMode<_, _> = Builder::new()
+        .size(DisplaySize128x32)
+        .connect(interface)
+        .into();
+    disp.reset(&mut rst, &mut delay).unwrap();
+    disp.init().unwrap();
+
+    let mut ds1302: DS1302<_, _, 1_000_000> =
+        DS1302::new_shared(&bus, ce, ds1302_mode::Hour24, NoWait, BitOrder::MsbFirst).unwrap();
+
+    let mut data = String::<U32>::new();
+    loop {
+        let (clock, calendar) = ds1302.get_clock_calendar().unwrap();
+        let _ = write!(data, "{}\n{}", calendar, clock);
+
+        Text::new(data.as_str(), Point::new(30, 10))
+            .into_styled(TextStyle::new(Font6x12, BinaryColor::On))
+            .draw(&mut disp)
+            .unwrap();
+        disp.flush().unwrap();
+        disp.clear();
+        data.clear();
+
+        delay.delay_ms(1000_u16);
+    }
+}
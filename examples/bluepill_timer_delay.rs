@@ -0,0 +1,149 @@
+//! DS1302 with a `Delay` on a hardware timer
+//!
+//! TIM2 counts freely at 1 MHz and its 16-bit counter is extended in software to the 32-bit
+//! instants of `Delay`. The driver starts a countdown of the CE inactive time after every
+//! transaction and waits for it before the next one, so two transactions are always at least
+//! 4 µs apart as the datasheet requires.
+
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use panic_probe as _;
+
+use stm32f1xx_hal::{
+    delay::Delay as HAL_DELAY,
+    pac::TIM2,
+    prelude::*,
+    serial::{Config, Serial},
+    spi::{Mode, Phase, Polarity, Spi},
+    stm32,
+    timer::{CountDownTimer, Timer},
+};
+
+use ds1302::{Delay, Mode as ds1302_mode, DS1302};
+
+use core::fmt::Write;
+use stm32f1xx_hal::spi::SpiBitFormat::LsbFirst;
+
+const TIMER_HZ: u32 = 1_000_000;
+
+struct TimerDelay {
+    timer: CountDownTimer<TIM2>,
+    last_cnt: u16,
+    ticks: u32,
+    deadline: Option<fugit::TimerInstantU32<TIMER_HZ>>,
+}
+
+impl TimerDelay {
+    // Let the timer count at TIMER_HZ over its whole 16-bit range
+    fn new(timer: Timer<TIM2>, timer_clock_hz: u32) -> Self {
+        let psc = (timer_clock_hz / TIMER_HZ - 1) as u16;
+        TimerDelay {
+            timer: timer.start_raw(psc, u16::MAX),
+            last_cnt: 0,
+            ticks: 0,
+            deadline: None,
+        }
+    }
+}
+
+impl Delay<TIMER_HZ> for TimerDelay {
+    type Error = core::convert::Infallible;
+
+    // The counter wraps every 65.536 ms. A wrap between two calls is missed, which makes the
+    // time run late: a countdown may last longer than asked, never shorter.
+    fn now(&mut self) -> fugit::TimerInstantU32<TIMER_HZ> {
+        let cnt = self.timer.cnt();
+        self.ticks = self
+            .ticks
+            .wrapping_add(u32::from(cnt.wrapping_sub(self.last_cnt)));
+        self.last_cnt = cnt;
+        fugit::TimerInstantU32::from_ticks(self.ticks)
+    }
+
+    fn start(&mut self, duration: fugit::TimerDurationU32<TIMER_HZ>) -> Result<(), Self::Error> {
+        self.deadline = Some(self.now() + duration);
+        Ok(())
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        match self.deadline {
+            Some(deadline) if self.now() < deadline => Err(nb::Error::WouldBlock),
+            // Nothing started yet, e.g. before the first transaction
+            _ => {
+                self.deadline = None;
+                Ok(())
+            }
+        }
+    }
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        self.deadline = None;
+        Ok(())
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let dp = stm32::Peripherals::take().unwrap();
+    let cp = cortex_m::peripheral::Peripherals::take().unwrap();
+
+    let mut flash = dp.FLASH.constrain();
+    let mut rcc = dp.RCC.constrain();
+
+    let clocks = rcc
+        .cfgr
+        .sysclk(16.mhz())
+        .pclk1(8.mhz())
+        .freeze(&mut flash.acr);
+
+    let mut afio = dp.AFIO.constrain();
+
+    let mut delay = HAL_DELAY::new(cp.SYST, clocks);
+    //ds1302 rtc
+    let mut gpioa = dp.GPIOA.split();
+    let cs = gpioa.pa4.into_push_pull_output(&mut gpioa.crl);
+    let sck = gpioa.pa5.into_alternate_push_pull(&mut gpioa.crl);
+    let miso = gpioa.pa6.into_floating_input(&mut gpioa.crl);
+    let mosi = gpioa.pa7.into_alternate_push_pull(&mut gpioa.crl);
+
+    let tx = gpioa.pa2.into_alternate_push_pull(&mut gpioa.crl);
+    let rx = gpioa.pa3;
+
+    let serial = Serial::usart2(
+        dp.USART2,
+        (tx, rx),
+        &mut afio.mapr,
+        Config::default().baudrate(9600.bps()),
+        clocks,
+    );
+    let (mut tx, _rx) = serial.split();
+
+    let spi_mode = Mode {
+        polarity: Polarity::IdleLow,
+        phase: Phase::CaptureOnFirstTransition,
+    };
+    let mut spi = Spi::spi1(
+        dp.SPI1,
+        (sck, miso, mosi),
+        &mut afio.mapr,
+        spi_mode,
+        500.khz(),
+        clocks,
+    );
+    spi.bit_format(LsbFirst);
+
+    // TIM2 is on APB1, its clock is pclk1 or twice pclk1 depending on the APB1 prescaler
+    let timer = Timer::tim2(dp.TIM2, &clocks);
+    let ds_timer = TimerDelay::new(timer, clocks.pclk1_tim().0);
+
+    let mut ds1302 = DS1302::new(spi, cs, ds1302_mode::Hour24, ds_timer).unwrap();
+
+    loop {
+        let datetime = ds1302.get_time_consistent().unwrap();
+        writeln!(tx, "{} {}", datetime.calendar, datetime.clock).unwrap();
+
+        delay.delay_ms(1000_u16);
+    }
+}
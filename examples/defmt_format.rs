@@ -0,0 +1,31 @@
+//! Prints the driver types over RTT with `defmt`.
+//!
+//! Build with `cargo run --example defmt_format --features defmt`. `defmt` also needs
+//! `"-C", "link-arg=-Tdefmt.x"` added to the `rustflags` in `.cargo/config`.
+
+#![no_main]
+#![no_std]
+
+use cortex_m_rt::entry;
+use defmt_rtt as _;
+use panic_probe as _;
+
+use ds1302::{Calendar, Clock, Hours, Mode};
+
+#[entry]
+fn main() -> ! {
+    let clk = Clock {
+        hours: Hours::Hour24(19),
+        minutes: 24,
+        seconds: 0,
+    };
+    let cal = Calendar {
+        day: 5,
+        date: 19,
+        month: 11,
+        year: 2021,
+    };
+    defmt::info!("{} {} {}", Mode::Hour24, clk, cal);
+
+    loop {}
+}
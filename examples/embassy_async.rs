@@ -0,0 +1,66 @@
+//! Blinks the Blue Pill LED with the seconds of the DS1302, using the async driver on Embassy.
+//!
+//! Build with `cargo run --example embassy_async --features async`. The DS1302 is wired as in the
+//! other examples: CE on PA4, SCLK on PA5 and I/O on PA6 (MISO) and PA7 (MOSI).
+
+#![no_main]
+#![no_std]
+
+use panic_probe as _;
+
+use core::convert::Infallible;
+use ds1302::{asynch::DS1302, Mode};
+use embassy_embedded_hal::shared_bus::asynch::spi::SpiDevice;
+use embassy_executor::Spawner;
+use embassy_stm32::gpio::{Level, Output, Speed};
+use embassy_stm32::spi::{self, BitOrder, Spi, MODE_0};
+use embassy_stm32::time::Hertz;
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::mutex::Mutex;
+use embassy_time::{Delay, Timer};
+
+/// CE pin of the DS1302, which is active high while the `SpiDevice` selects a chip with a low pin
+struct ActiveHigh<'d>(Output<'d>);
+
+impl embedded_hal_1::digital::ErrorType for ActiveHigh<'_> {
+    type Error = Infallible;
+}
+
+impl embedded_hal_1::digital::OutputPin for ActiveHigh<'_> {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.0.set_high();
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.0.set_low();
+        Ok(())
+    }
+}
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_stm32::init(Default::default());
+
+    let mut config = spi::Config::default();
+    config.mode = MODE_0;
+    config.bit_order = BitOrder::LsbFirst;
+    config.frequency = Hertz(500_000);
+    let bus = Spi::new(p.SPI1, p.PA5, p.PA7, p.PA6, p.DMA1_CH3, p.DMA1_CH2, config);
+    let bus: Mutex<NoopRawMutex, _> = Mutex::new(bus);
+    let cs = ActiveHigh(Output::new(p.PA4, Level::Low, Speed::Low));
+    let spi = SpiDevice::new(&bus, cs);
+
+    let mut rtc = DS1302::new(spi, Mode::Hour24, Delay).await.unwrap();
+    let mut led = Output::new(p.PC13, Level::High, Speed::Low);
+
+    loop {
+        let clock = rtc.get_clock().await.unwrap();
+        if clock.seconds % 2 == 0 {
+            led.set_low();
+        } else {
+            led.set_high();
+        }
+        Timer::after_millis(250).await;
+    }
+}
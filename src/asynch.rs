@@ -0,0 +1,237 @@
+//! Async DS1302 driver based on [`embedded-hal-async`]
+//!
+//! The register encoding is shared with the blocking [`DS1302`](crate::DS1302) driver, only the bus
+//! access is awaited. The CE inactive time between two transactions is awaited on a [`DelayNs`].
+//!
+//! [`embedded-hal-async`]: https://github.com/rust-embedded/embedded-hal
+
+use crate::date::{datetime_to_unix, unix_to_datetime};
+use crate::driver::driver_methods;
+use crate::ram::{crc8, crc_region_is_valid, RamBuffer};
+use crate::registers::{tc_is_safe, Command, Register, TrickleCharger};
+use crate::{
+    bcd_to_decimal_checked, clock_calendar_is_bcd, clock_is_bcd, days_in_month, decimal_to_bcd,
+    decode_burst, decode_clock, encode_clock_calendar, hours_bcd, hours_in_mode, splice_calendar,
+    weekday, BitOrder, Calendar, Clock, DateTime, Ds, Ds1302Error, Hours, Mode, Rs, Weekday,
+    CLOCK_HALT_FLAG, HOUR_12_BIT, RAM_SIZE, SECOND_CHANGE_POLLS, WRITE_PROTECT_BIT,
+};
+use core::convert::{Infallible, TryFrom};
+use core::ops::Range;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::spi::SpiDevice;
+
+/// CE inactive time in microseconds
+const CE_INACTIVE_US: u32 = crate::T_CE_INACTIVE_NS / 1_000;
+
+///DS1302 RTCC async driver
+///
+///Like with the [blocking driver](crate::DS1302), combining the getters of a single field can
+///straddle a rollover, [`DS1302::get_datetime`] reads all fields in one burst.
+pub struct DS1302<SPI, D> {
+    spi: SPI,
+    delay: D,
+    bit_order: BitOrder,
+    // CE inactive time between two transactions
+    ce_delay_us: u32,
+    // Last known state of the write protect bit, `None` until it has been read
+    write_protect: Option<bool>,
+    // CH flag found set by the constructor
+    clock_was_halted: bool,
+    // Hour format of the last write to the hours register
+    mode: Mode,
+}
+
+impl<SPI, D> DS1302<SPI, D>
+where
+    SPI: SpiDevice<u8>,
+    D: DelayNs,
+{
+    ///Creates new instance DS1302 RTC
+    pub async fn new(
+        spi: SPI,
+        mode: Mode,
+        delay: D,
+    ) -> Result<Self, Ds1302Error<SPI::Error, Infallible>> {
+        DS1302::init(spi, mode, delay, BitOrder::LsbFirst, CE_INACTIVE_US, true).await
+    }
+    ///Creates new instance DS1302 RTC on a SPI bus that sends MSB first, the bits are reversed in software
+    pub async fn new_msb_first(
+        spi: SPI,
+        mode: Mode,
+        delay: D,
+    ) -> Result<Self, Ds1302Error<SPI::Error, Infallible>> {
+        DS1302::init(spi, mode, delay, BitOrder::MsbFirst, CE_INACTIVE_US, true).await
+    }
+    ///Creates new instance DS1302 RTC waiting `ce_delay_us` microseconds between two transactions
+    ///
+    ///The datasheet minimum of the CE inactive time is [`T_CE_INACTIVE_NS`](crate::T_CE_INACTIVE_NS),
+    ///which is the default of [`DS1302::new`].
+    ///A longer time can help with long wires or at extreme temperatures.
+    pub async fn new_with_timing(
+        spi: SPI,
+        mode: Mode,
+        delay: D,
+        ce_delay_us: u32,
+    ) -> Result<Self, Ds1302Error<SPI::Error, Infallible>> {
+        DS1302::init(spi, mode, delay, BitOrder::LsbFirst, ce_delay_us, true).await
+    }
+    ///Creates new instance DS1302 RTC without starting the oscillator
+    ///
+    ///A halted clock stays halted, [`DS1302::resume`] starts it and [`DS1302::clock_was_halted`]
+    ///tells whether it was found halted. Only the hour format is set like in [`DS1302::new`].
+    pub async fn new_preserve_state(
+        spi: SPI,
+        mode: Mode,
+        delay: D,
+    ) -> Result<Self, Ds1302Error<SPI::Error, Infallible>> {
+        DS1302::init(spi, mode, delay, BitOrder::LsbFirst, CE_INACTIVE_US, false).await
+    }
+
+    async fn init(
+        spi: SPI,
+        mode: Mode,
+        delay: D,
+        bit_order: BitOrder,
+        ce_delay_us: u32,
+        start_clock: bool,
+    ) -> Result<Self, Ds1302Error<SPI::Error, Infallible>> {
+        let mut ds1302 = DS1302 {
+            spi,
+            delay,
+            bit_order,
+            ce_delay_us,
+            write_protect: None,
+            clock_was_halted: false,
+            mode,
+        };
+        // Check CLOCK HALT FLAG bit
+        let byte = ds1302.read_reg(Register::SECONDS.addr()).await?;
+        ds1302.clock_was_halted = (byte & CLOCK_HALT_FLAG) != 0;
+        // Reset CLOCK HALT FLAG bit, power on device
+        if ds1302.clock_was_halted && start_clock {
+            ds1302.write_reg(Register::SECONDS.addr(), 0).await?;
+            let byte = ds1302.read_reg(Register::SECONDS.addr()).await?;
+            if (byte & CLOCK_HALT_FLAG) != 0 {
+                return Err(Ds1302Error::Unknown);
+            }
+        }
+        ds1302.set_clock_mode(mode).await?;
+        Ok(ds1302)
+    }
+    ///Delete DS1302 RTC instance and return SPI device and delay
+    pub fn destroy(self) -> (SPI, D) {
+        (self.spi, self.delay)
+    }
+
+    async fn transfer(
+        &mut self,
+        bytes: &mut [u8],
+    ) -> Result<(), Ds1302Error<SPI::Error, Infallible>> {
+        // The command byte is overwritten by the byte read while it is sent
+        #[cfg(feature = "log")]
+        let command = bytes[0];
+        self.bit_order.apply(bytes);
+        self.spi
+            .transfer_in_place(bytes)
+            .await
+            .map_err(Ds1302Error::Spi)?;
+        self.delay.delay_us(self.ce_delay_us).await;
+        self.bit_order.apply(bytes);
+        #[cfg(feature = "log")]
+        log::trace!("ds1302 read {:#04x}: {:02x?}", command, &bytes[1..]);
+        Ok(())
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), Ds1302Error<SPI::Error, Infallible>> {
+        let mut buf = [0_u8; RAM_SIZE + 1];
+        let buf = &mut buf[..bytes.len()];
+        buf.copy_from_slice(bytes);
+        self.bit_order.apply(buf);
+        self.spi.write(buf).await.map_err(Ds1302Error::Spi)?;
+        self.delay.delay_us(self.ce_delay_us).await;
+        #[cfg(feature = "log")]
+        log::trace!("ds1302 write {:#04x}: {:02x?}", bytes[0], &bytes[1..]);
+        self.track_mode(bytes);
+        Ok(())
+    }
+
+    driver_methods!(Ds1302Error<SPI::Error, Infallible>, AsyncFnOnce, async await);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::asynch::{block_on, read, write};
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+    use embedded_hal_mock::eh1::spi::{Mock, Transaction};
+    use std::vec;
+    use std::vec::Vec;
+
+    #[test]
+    fn new_restarts_a_halted_clock_from_zero_seconds() {
+        let expected: Vec<_> = [
+            read(0x80, 0x80),
+            read(0x8E, 0x00),
+            write(0x80, 0x00),
+            read(0x80, 0x00),
+            read(0x84, 0x12),
+        ]
+        .concat();
+        let mut spi = Mock::new(&expected);
+        let rtc = block_on(DS1302::new(spi.clone(), Mode::Hour24, NoopDelay::new())).unwrap();
+        assert!(rtc.clock_was_halted());
+        rtc.destroy();
+        spi.done();
+    }
+
+    #[test]
+    fn new_preserve_state_leaves_a_halted_clock_halted() {
+        let expected: Vec<_> = [read(0x80, 0x80), read(0x84, 0x12)].concat();
+        let mut spi = Mock::new(&expected);
+        let rtc = block_on(DS1302::new_preserve_state(
+            spi.clone(),
+            Mode::Hour24,
+            NoopDelay::new(),
+        ))
+        .unwrap();
+        assert!(rtc.clock_was_halted());
+        rtc.destroy();
+        spi.done();
+    }
+
+    #[test]
+    fn new_msb_first_reverses_the_bits_of_every_byte() {
+        let expected = [
+            // Seconds read, 0x81 is its own reverse
+            Transaction::transaction_start(),
+            Transaction::transfer_in_place(vec![0x81, 0], vec![0, 0x00]),
+            Transaction::transaction_end(),
+            // Hours read of 0x85 answering 12 in 24-hour format
+            Transaction::transaction_start(),
+            Transaction::transfer_in_place(vec![0xA1, 0], vec![0, 0x48]),
+            Transaction::transaction_end(),
+            // Hours read by get_hours
+            Transaction::transaction_start(),
+            Transaction::transfer_in_place(vec![0xA1, 0], vec![0, 0x48]),
+            Transaction::transaction_end(),
+            // Write protect read of 0x8F, then write of 34 minutes at 0x82
+            Transaction::transaction_start(),
+            Transaction::transfer_in_place(vec![0xF1, 0], vec![0, 0x00]),
+            Transaction::transaction_end(),
+            Transaction::transaction_start(),
+            Transaction::write_vec(vec![0x41, 0x2C]),
+            Transaction::transaction_end(),
+        ];
+        let mut spi = Mock::new(&expected);
+        let mut rtc = block_on(DS1302::new_msb_first(
+            spi.clone(),
+            Mode::Hour24,
+            NoopDelay::new(),
+        ))
+        .unwrap();
+        assert_eq!(block_on(rtc.get_hours()).unwrap(), Hours::Hour24(12));
+        block_on(rtc.set_minutes(34)).unwrap();
+        rtc.destroy();
+        spi.done();
+    }
+}
@@ -0,0 +1,149 @@
+//! Builder configuring the driver before it talks to the chip
+
+use crate::{
+    BitOrder, CsPolarity, Delay, DriverError, Ds, Interface, Mode, Rs, SpiInterface, DS1302,
+    T_CE_INACTIVE_NS,
+};
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+use fugit::ExtU32;
+
+/// Builder of a [`DS1302`] driver, for the options that [`DS1302::new`] leaves at their default
+///
+/// ```ignore
+/// let rtc = DS1302Builder::new(Mode::Hour24)
+///     .trickle_charger(Ds::ONE07V, Rs::R8K)
+///     .ce_delay(10.micros())
+///     .build(spi, cs, timer)?;
+/// ```
+///
+/// `build` starts a halted oscillator and sets the hour format like `new`, then enables the
+/// trickle charger if one is configured.
+#[derive(Debug, Clone, Copy)]
+pub struct DS1302Builder<const TIMER_HZ: u32> {
+    mode: Mode,
+    trickle_charger: Option<(Ds, Rs)>,
+    ce_delay: fugit::TimerDurationU32<TIMER_HZ>,
+    bit_order: BitOrder,
+    cs_polarity: CsPolarity,
+}
+
+impl<const TIMER_HZ: u32> DS1302Builder<TIMER_HZ> {
+    /// Start with the hour format, the other options are the defaults of [`DS1302::new`]
+    pub fn new(mode: Mode) -> Self {
+        DS1302Builder {
+            mode,
+            trickle_charger: None,
+            ce_delay: (T_CE_INACTIVE_NS / 1_000).micros(),
+            bit_order: BitOrder::LsbFirst,
+            cs_polarity: CsPolarity::ActiveHigh,
+        }
+    }
+
+    /// Enable the trickle charger with the diode `ds` and the resistor `rs`,
+    /// see [`DS1302::tc_enable`]. By default the charger register is left untouched.
+    pub fn trickle_charger(mut self, ds: Ds, rs: Rs) -> Self {
+        self.trickle_charger = Some((ds, rs));
+        self
+    }
+
+    /// Wait `ce_delay` between two transactions instead of [`T_CE_INACTIVE_NS`],
+    /// see [`DS1302::new_with_timing`]
+    pub fn ce_delay(mut self, ce_delay: fugit::TimerDurationU32<TIMER_HZ>) -> Self {
+        self.ce_delay = ce_delay;
+        self
+    }
+
+    /// Bit order of the SPI bus, see [`DS1302::new_msb_first`]
+    pub fn bit_order(mut self, bit_order: BitOrder) -> Self {
+        self.bit_order = bit_order;
+        self
+    }
+
+    /// Polarity of the chip select pin used by [`DS1302Builder::build`]
+    pub fn cs_polarity(mut self, cs_polarity: CsPolarity) -> Self {
+        self.cs_polarity = cs_polarity;
+        self
+    }
+
+    /// Create the driver on an `embedded-hal` 0.2 SPI bus and chip select pin
+    #[allow(clippy::type_complexity)]
+    pub fn build<SPI, CS, E, PinError, CLK>(
+        self,
+        spi: SPI,
+        cs: CS,
+        timer: CLK,
+    ) -> Result<
+        DS1302<SpiInterface<SPI, CS>, CLK, TIMER_HZ>,
+        DriverError<SpiInterface<SPI, CS>, CLK, TIMER_HZ>,
+    >
+    where
+        SPI: spi::Transfer<u8, Error = E> + spi::Write<u8, Error = E>,
+        CS: OutputPin<Error = PinError>,
+        CLK: Delay<TIMER_HZ>,
+    {
+        let iface = SpiInterface::with_cs_polarity(spi, cs, self.cs_polarity);
+        self.build_with_interface(iface, timer)
+    }
+
+    /// Create the driver on another [`Interface`], the chip select polarity is up to it
+    pub fn build_with_interface<IF, CLK>(
+        self,
+        iface: IF,
+        timer: CLK,
+    ) -> Result<DS1302<IF, CLK, TIMER_HZ>, DriverError<IF, CLK, TIMER_HZ>>
+    where
+        IF: Interface,
+        CLK: Delay<TIMER_HZ>,
+    {
+        let mut ds1302 =
+            DS1302::init(iface, self.mode, timer, self.bit_order, self.ce_delay, true)?;
+        if let Some((ds, rs)) = self.trickle_charger {
+            ds1302.tc_enable(ds, rs)?;
+        }
+        Ok(ds1302)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{read, write, Pin, Recorder};
+    use embedded_hal_mock::eh0::spi::Mock;
+
+    #[test]
+    fn build_applies_the_options_in_order() {
+        let mut spi = Mock::new(&[
+            // Halted chip: the oscillator is started first
+            read(0x80, 0x80),
+            read(0x8E, 0x80),
+            write(0x8E, 0x00),
+            write(0x80, 0x00),
+            read(0x80, 0x00),
+            // Then the hour format, noon becomes 12 PM
+            read(0x84, 0x12),
+            write(0x84, 0xB2),
+            // Then the trickle charger
+            write(0x90, 0xAB),
+        ]);
+        let rtc = DS1302Builder::new(Mode::Hour12)
+            .trickle_charger(Ds::TWO14V, Rs::R8K)
+            .ce_delay(10.micros())
+            .build(spi.clone(), Pin::default(), Recorder::default())
+            .unwrap();
+        assert!(rtc.clock_was_halted());
+        let (_, _, timer) = rtc.destroy().unwrap();
+        // One countdown per transaction
+        assert_eq!(timer.started, [10; 8]);
+        spi.done();
+    }
+
+    #[test]
+    fn build_leaves_the_trickle_charger_alone_by_default() {
+        let mut spi = Mock::new(&[read(0x80, 0x00), read(0x84, 0x12)]);
+        DS1302Builder::new(Mode::Hour24)
+            .build(spi.clone(), Pin::default(), Recorder::default())
+            .unwrap();
+        spi.done();
+    }
+}
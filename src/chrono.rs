@@ -0,0 +1,161 @@
+//! [`chrono`](https://docs.rs/chrono) interop, enabled by the `chrono` feature
+//!
+//! [`Calendar`] converts to and from [`NaiveDate`], [`Clock`] to and from [`NaiveTime`].
+//! [`DateTime`] converts to and from [`NaiveDateTime`], [`to_naive_datetime`] and
+//! [`from_naive_datetime`] do the same for a separate clock and calendar.
+//!
+//! The chip stores the year as two BCD digits, so only the years 2000..=2099 can be converted
+//! to the DS1302 format. The day of the week is a plain 1..=7 counter on the chip; the conversions
+//! use the ISO numbering, Monday is 1 and Sunday is 7.
+
+use crate::{Calendar, Clock, DateTime, Ds1302Error, Hours};
+use ::chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use core::convert::TryFrom;
+
+impl TryFrom<Calendar> for NaiveDate {
+    type Error = Ds1302Error;
+
+    /// Fails if the calendar is not a valid date. The `day` field is ignored.
+    fn try_from(calendar: Calendar) -> Result<Self, Self::Error> {
+        NaiveDate::from_ymd_opt(
+            i32::from(calendar.year),
+            u32::from(calendar.month),
+            u32::from(calendar.date),
+        )
+        .ok_or(Ds1302Error::Parameter)
+    }
+}
+
+impl TryFrom<NaiveDate> for Calendar {
+    type Error = Ds1302Error;
+
+    /// Fails if the year is outside of 2000..=2099. `day` is set to the ISO weekday of the date.
+    fn try_from(date: NaiveDate) -> Result<Self, Self::Error> {
+        if !(2000..=2099).contains(&date.year()) {
+            return Err(Ds1302Error::Parameter);
+        }
+        Ok(Calendar {
+            day: date.weekday().number_from_monday() as u8,
+            date: date.day() as u8,
+            month: date.month() as u8,
+            year: date.year() as u16,
+        })
+    }
+}
+
+impl TryFrom<Clock> for NaiveTime {
+    type Error = Ds1302Error;
+
+    /// 12-hour clocks are converted to 24-hour first. Fails if the clock is not a valid time.
+    fn try_from(clock: Clock) -> Result<Self, Self::Error> {
+        if !clock.hours.is_valid() {
+            return Err(Ds1302Error::Parameter);
+        }
+        NaiveTime::from_hms_opt(
+            u32::from(clock.hours.hour24()),
+            u32::from(clock.minutes),
+            u32::from(clock.seconds),
+        )
+        .ok_or(Ds1302Error::Parameter)
+    }
+}
+
+impl From<NaiveTime> for Clock {
+    /// The clock is in 24-hour format, a leap second is truncated to 59
+    fn from(time: NaiveTime) -> Self {
+        Clock {
+            hours: Hours::Hour24(time.hour() as u8),
+            minutes: time.minute() as u8,
+            seconds: time.second() as u8,
+        }
+    }
+}
+
+/// Combine a clock and a calendar read from the chip into a [`NaiveDateTime`]
+///
+/// Fails if either of them is not valid, see [`NaiveDate::try_from`] and [`NaiveTime::try_from`].
+pub fn to_naive_datetime(clock: Clock, calendar: Calendar) -> Result<NaiveDateTime, Ds1302Error> {
+    let date = NaiveDate::try_from(calendar)?;
+    let time = NaiveTime::try_from(clock)?;
+    Ok(date.and_time(time))
+}
+
+/// Split a [`NaiveDateTime`] into a 24-hour clock and a calendar that can be written to the chip
+///
+/// Fails if the year is outside of 2000..=2099.
+pub fn from_naive_datetime(datetime: NaiveDateTime) -> Result<(Clock, Calendar), Ds1302Error> {
+    let calendar = Calendar::try_from(datetime.date())?;
+    Ok((Clock::from(datetime.time()), calendar))
+}
+
+impl TryFrom<DateTime> for NaiveDateTime {
+    type Error = Ds1302Error;
+
+    fn try_from(datetime: DateTime) -> Result<Self, Self::Error> {
+        to_naive_datetime(datetime.clock, datetime.calendar)
+    }
+}
+
+impl TryFrom<NaiveDateTime> for DateTime {
+    type Error = Ds1302Error;
+
+    fn try_from(datetime: NaiveDateTime) -> Result<Self, Self::Error> {
+        let (clock, calendar) = from_naive_datetime(datetime)?;
+        Ok(DateTime { clock, calendar })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(y: i32, mo: u32, d: u32, h: u32, mi: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, mo, d)
+            .unwrap()
+            .and_hms_opt(h, mi, s)
+            .unwrap()
+    }
+
+    #[test]
+    fn datetimes_round_trip() {
+        for datetime in [
+            naive(2000, 1, 1, 0, 0, 0),
+            naive(2024, 2, 29, 12, 30, 45),
+            naive(2021, 11, 19, 19, 24, 0),
+            naive(2099, 12, 31, 23, 59, 59),
+        ] {
+            let (clock, calendar) = from_naive_datetime(datetime).unwrap();
+            assert_eq!(to_naive_datetime(clock, calendar).unwrap(), datetime);
+        }
+    }
+
+    #[test]
+    fn leap_day_has_its_weekday() {
+        let (_, calendar) = from_naive_datetime(naive(2024, 2, 29, 0, 0, 0)).unwrap();
+        // Thursday
+        assert_eq!((calendar.day, calendar.date, calendar.month), (4, 29, 2));
+    }
+
+    #[test]
+    fn twelve_hour_clocks_convert_to_the_same_time() {
+        let calendar = Calendar::new(5, 19, 11, 2021).unwrap();
+        let pm = Clock::new(Hours::Hour12pm(7), 24, 0).unwrap();
+        let midnight = Clock::new(Hours::Hour12am(12), 0, 0).unwrap();
+        assert_eq!(
+            to_naive_datetime(pm, calendar).unwrap(),
+            naive(2021, 11, 19, 19, 24, 0)
+        );
+        assert_eq!(
+            to_naive_datetime(midnight, calendar).unwrap(),
+            naive(2021, 11, 19, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn years_outside_the_chip_range_are_rejected() {
+        for year in [1999, 2100] {
+            let result = from_naive_datetime(naive(year, 6, 1, 0, 0, 0));
+            assert!(matches!(result, Err(Ds1302Error::Parameter)));
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! Calendar arithmetic on the values stored by the chip
+
+use crate::{Calendar, Clock, DateTime, Ds1302Error};
+use core::convert::TryFrom;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+// 2099-12-31 23:59:59, the last second the chip can hold
+pub(crate) const LAST_UNIX_SECOND: u64 = 4_102_444_799;
+
+/// Day of the week of a date, 1 (Monday) ..= 7 (Sunday) like ISO 8601
+///
+/// The chip doesn't derive the day register from the date, this computes it with Sakamoto's
+/// method. Returns `None` if `month` is not 1..=12.
+pub fn weekday(year: u16, month: u8, date: u8) -> Option<u8> {
+    const T: [u32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    if !(1..=12).contains(&month) {
+        return None;
+    }
+    // Shifted by a whole 400-year cycle, the weekdays repeat and year 0 doesn't underflow
+    let y = u32::from(year) + 400 - if month < 3 { 1 } else { 0 };
+    let d = (y + y / 4 - y / 100 + y / 400 + T[month as usize - 1] + u32::from(date)) % 7;
+    // 0 is Sunday
+    Some(if d == 0 { 7 } else { d as u8 })
+}
+
+/// Gregorian leap year
+// `is_multiple_of` needs Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+pub fn is_leap_year(year: u16) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Number of days in a month, February has 29 days in leap years
+///
+/// Returns `None` if `month` is not 1..=12.
+pub fn days_in_month(year: u16, month: u8) -> Option<u8> {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => Some(31),
+        4 | 6 | 9 | 11 => Some(30),
+        2 if is_leap_year(year) => Some(29),
+        2 => Some(28),
+        _ => None,
+    }
+}
+
+// Day of the year 1..=366, months out of range count as empty
+pub(crate) fn day_of_year(year: u16, month: u8, date: u8) -> u16 {
+    let before: u16 = (1..month)
+        .map(|m| u16::from(days_in_month(year, m).unwrap_or(0)))
+        .sum();
+    before + u16::from(date)
+}
+
+// ISO 8601 weeks of a year: 53 if it starts on a Thursday, or on a Wednesday in a leap year
+fn iso_weeks_in_year(year: u16) -> u8 {
+    match weekday(year, 1, 1) {
+        Some(4) => 53,
+        Some(3) if is_leap_year(year) => 53,
+        _ => 52,
+    }
+}
+
+// ISO 8601 week number 1..=53 of a valid date, the first week contains the first Thursday
+pub(crate) fn iso_week(year: u16, month: u8, date: u8) -> u8 {
+    let doy = i32::from(day_of_year(year, month, date));
+    let wd = i32::from(weekday(year, month, date).unwrap_or(1));
+    let week = (doy - wd + 10) / 7;
+    if week < 1 {
+        iso_weeks_in_year(year.wrapping_sub(1))
+    } else if week > i32::from(iso_weeks_in_year(year)) {
+        1
+    } else {
+        week as u8
+    }
+}
+
+// Days from 1970-01-01 to a valid date that isn't before it
+fn days_since_epoch(year: u16, month: u8, date: u8) -> u64 {
+    let mut days = 0_u64;
+    for y in 1970..year {
+        days += if is_leap_year(y) { 366 } else { 365 };
+    }
+    for m in 1..month {
+        days += u64::from(days_in_month(year, m).unwrap_or(0));
+    }
+    days + u64::from(date) - 1
+}
+
+// Seconds since 1970-01-01 00:00:00 UTC, `None` if the date or time isn't valid
+pub(crate) fn datetime_to_unix(datetime: &DateTime) -> Option<u64> {
+    let clock = &datetime.clock;
+    let calendar = &datetime.calendar;
+    let days = days_in_month(calendar.year, calendar.month)?;
+    if calendar.year < 1970 || !(1..=days).contains(&calendar.date) || !clock.is_valid() {
+        return None;
+    }
+    let days = days_since_epoch(calendar.year, calendar.month, calendar.date);
+    Some(days * SECONDS_PER_DAY + u64::from(clock.seconds_since_midnight()))
+}
+
+// 24-hour date and time of a Unix timestamp, `None` outside of the years 2000..=2099
+pub(crate) fn unix_to_datetime(secs: u64) -> Option<DateTime> {
+    let mut days = secs / SECONDS_PER_DAY;
+    let seconds = secs % SECONDS_PER_DAY;
+
+    let mut year = 1970_u16;
+    loop {
+        let year_days = if is_leap_year(year) { 366 } else { 365 };
+        if days < year_days {
+            break;
+        }
+        days -= year_days;
+        year += 1;
+        if year > 2099 {
+            return None;
+        }
+    }
+    if year < 2000 {
+        return None;
+    }
+    let mut month = 1_u8;
+    loop {
+        let month_days = u64::from(days_in_month(year, month)?);
+        if days < month_days {
+            break;
+        }
+        days -= month_days;
+        month += 1;
+    }
+    let date = days as u8 + 1;
+
+    Some(DateTime {
+        clock: Clock::from_seconds_since_midnight(seconds as u32).ok()?,
+        calendar: Calendar {
+            day: weekday(year, month, date)?,
+            date,
+            month,
+            year,
+        },
+    })
+}
+
+/// Three letter English name of a day of the week numbered like [`weekday`], e.g. "Mon" for 1.
+/// Returns `None` if `day` is not 1..=7.
+pub fn weekday_short_name(day: u8) -> Option<&'static str> {
+    Weekday::try_from(day).ok().map(|day| day.short_name())
+}
+
+/// Day of the week, numbered 1 (Monday) ..= 7 (Sunday) like [`weekday`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Weekday {
+    Monday = 1,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    /// Three letter English name, e.g. "Mon"
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Weekday::Monday => "Mon",
+            Weekday::Tuesday => "Tue",
+            Weekday::Wednesday => "Wed",
+            Weekday::Thursday => "Thu",
+            Weekday::Friday => "Fri",
+            Weekday::Saturday => "Sat",
+            Weekday::Sunday => "Sun",
+        }
+    }
+}
+
+impl TryFrom<u8> for Weekday {
+    type Error = Ds1302Error;
+
+    /// Fails with `Ds1302Error::Parameter` if `day` is not 1..=7
+    fn try_from(day: u8) -> Result<Self, Self::Error> {
+        match day {
+            1 => Ok(Weekday::Monday),
+            2 => Ok(Weekday::Tuesday),
+            3 => Ok(Weekday::Wednesday),
+            4 => Ok(Weekday::Thursday),
+            5 => Ok(Weekday::Friday),
+            6 => Ok(Weekday::Saturday),
+            7 => Ok(Weekday::Sunday),
+            _ => Err(Ds1302Error::Parameter),
+        }
+    }
+}
+
+impl From<Weekday> for u8 {
+    fn from(day: Weekday) -> Self {
+        day as u8
+    }
+}
+
+/// Month of the year, numbered 1 (January) ..= 12 (December) like the chip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Month {
+    January = 1,
+    February,
+    March,
+    April,
+    May,
+    June,
+    July,
+    August,
+    September,
+    October,
+    November,
+    December,
+}
+
+impl Month {
+    /// Three letter English name, e.g. "Jan"
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Month::January => "Jan",
+            Month::February => "Feb",
+            Month::March => "Mar",
+            Month::April => "Apr",
+            Month::May => "May",
+            Month::June => "Jun",
+            Month::July => "Jul",
+            Month::August => "Aug",
+            Month::September => "Sep",
+            Month::October => "Oct",
+            Month::November => "Nov",
+            Month::December => "Dec",
+        }
+    }
+}
+
+impl TryFrom<u8> for Month {
+    type Error = Ds1302Error;
+
+    /// Fails with `Ds1302Error::Parameter` if `month` is not 1..=12
+    fn try_from(month: u8) -> Result<Self, Self::Error> {
+        match month {
+            1 => Ok(Month::January),
+            2 => Ok(Month::February),
+            3 => Ok(Month::March),
+            4 => Ok(Month::April),
+            5 => Ok(Month::May),
+            6 => Ok(Month::June),
+            7 => Ok(Month::July),
+            8 => Ok(Month::August),
+            9 => Ok(Month::September),
+            10 => Ok(Month::October),
+            11 => Ok(Month::November),
+            12 => Ok(Month::December),
+            _ => Err(Ds1302Error::Parameter),
+        }
+    }
+}
+
+impl From<Month> for u8 {
+    fn from(month: Month) -> Self {
+        month as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hours;
+
+    #[test]
+    fn weekday_of_known_dates() {
+        // Friday
+        assert_eq!(weekday(2021, 11, 19), Some(5));
+        // Saturday, the first day the chip can store
+        assert_eq!(weekday(2000, 1, 1), Some(6));
+        // 2000 is a leap year although it is a century, Tuesday
+        assert_eq!(weekday(2000, 2, 29), Some(2));
+        assert_eq!(weekday(2000, 3, 1), Some(3));
+        // Thursday
+        assert_eq!(weekday(2024, 2, 29), Some(4));
+        // Thursday, the last day the chip can store
+        assert_eq!(weekday(2099, 12, 31), Some(4));
+        // Sunday is 7
+        assert_eq!(weekday(2023, 1, 1), Some(7));
+    }
+
+    #[test]
+    fn weekday_rejects_months_out_of_range() {
+        assert_eq!(weekday(2021, 0, 1), None);
+        assert_eq!(weekday(2021, 13, 1), None);
+    }
+
+    #[test]
+    fn february_has_29_days_in_leap_years_only() {
+        assert_eq!(days_in_month(2024, 2), Some(29));
+        assert_eq!(days_in_month(2000, 2), Some(29));
+        assert_eq!(days_in_month(2023, 2), Some(28));
+        assert_eq!(days_in_month(2100, 2), Some(28));
+    }
+
+    #[test]
+    fn days_in_month_of_the_other_months() {
+        for month in [4, 6, 9, 11] {
+            assert_eq!(days_in_month(2021, month), Some(30));
+        }
+        for month in [1, 3, 5, 7, 8, 10, 12] {
+            assert_eq!(days_in_month(2021, month), Some(31));
+        }
+        assert_eq!(days_in_month(2021, 0), None);
+        assert_eq!(days_in_month(2021, 13), None);
+    }
+
+    fn datetime(year: u16, month: u8, date: u8, hour: u8, minutes: u8, seconds: u8) -> DateTime {
+        DateTime {
+            clock: Clock::new(Hours::Hour24(hour), minutes, seconds).unwrap(),
+            calendar: Calendar {
+                day: weekday(year, month, date).unwrap(),
+                date,
+                month,
+                year,
+            },
+        }
+    }
+
+    #[test]
+    fn known_unix_timestamps() {
+        for (secs, expected) in [
+            (946_684_800, datetime(2000, 1, 1, 0, 0, 0)),
+            (1_637_349_840, datetime(2021, 11, 19, 19, 24, 0)),
+            (1_709_208_000, datetime(2024, 2, 29, 12, 0, 0)),
+            (4_102_444_799, datetime(2099, 12, 31, 23, 59, 59)),
+        ] {
+            let decoded = unix_to_datetime(secs).unwrap();
+            assert_eq!(decoded, expected);
+            assert_eq!(decoded.calendar.day, expected.calendar.day);
+            assert_eq!(datetime_to_unix(&decoded), Some(secs));
+        }
+    }
+
+    #[test]
+    fn unix_timestamps_outside_the_chip_years_are_rejected() {
+        assert_eq!(unix_to_datetime(0), None);
+        assert_eq!(unix_to_datetime(946_684_799), None);
+        assert_eq!(unix_to_datetime(4_102_444_800), None);
+    }
+
+    #[test]
+    fn weekdays_are_numbered_from_monday() {
+        assert_eq!(Weekday::try_from(1).unwrap(), Weekday::Monday);
+        assert_eq!(Weekday::try_from(7).unwrap(), Weekday::Sunday);
+        for day in 1..=7 {
+            assert_eq!(u8::from(Weekday::try_from(day).unwrap()), day);
+        }
+        assert!(Weekday::try_from(0).is_err());
+        assert!(Weekday::try_from(8).is_err());
+        assert_eq!(Weekday::Sunday.short_name(), "Sun");
+        assert_eq!(weekday_short_name(1), Some("Mon"));
+        assert_eq!(weekday_short_name(0), None);
+    }
+
+    #[test]
+    fn months_are_numbered_from_january() {
+        assert_eq!(Month::try_from(1).unwrap(), Month::January);
+        assert_eq!(Month::try_from(12).unwrap(), Month::December);
+        for month in 1..=12 {
+            assert_eq!(u8::from(Month::try_from(month).unwrap()), month);
+        }
+        assert!(Month::try_from(0).is_err());
+        assert!(Month::try_from(13).is_err());
+        assert_eq!(Month::September.short_name(), "Sep");
+    }
+
+    #[test]
+    fn weekday_short_names_of_every_day() {
+        let names = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+        for (day, name) in (1..=7).zip(names) {
+            assert_eq!(weekday_short_name(day), Some(name));
+        }
+        assert_eq!(weekday_short_name(8), None);
+        assert_eq!(weekday_short_name(0xFF), None);
+    }
+}
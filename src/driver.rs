@@ -0,0 +1,1290 @@
+//! Methods shared by the blocking and the async driver
+//!
+//! Both drivers are generated from the same bodies, so that they can't drift apart. Only the
+//! construction and the bus access, `transfer` and `write`, are written for each driver.
+
+// Expands to the methods of a driver returning `$error`, `$fn_once` is the closure trait taken by
+// `with_writes_enabled`. Pass `AsyncFnOnce, async await` for the async driver.
+// Every call of a bus method is followed by `$(.$await)?`, which is `.await` in the async driver
+// and nothing in the blocking one.
+macro_rules! driver_methods {
+    ($error:ty, $fn_once:ident $(, $async:ident $await:ident)?) => {
+        ///Whether the oscillator was halted when the driver was created
+        ///
+        ///The chip powers up halted, so this usually means the backup supply was lost and the
+        ///clock and calendar are stale. The constructor restarts the oscillator from 0 seconds,
+        ///except for `new_preserve_state`; the time should be set again, e.g. from a network
+        ///source.
+        pub fn clock_was_halted(&self) -> bool {
+            self.clock_was_halted
+        }
+
+        ///Hour format last set through the driver, the chip isn't read
+        ///
+        ///It is set by the constructor and by every write of the hours register: `set_clock_mode`,
+        ///the hours setters, the burst setters, `import_state` and `write_register`.
+        ///
+        ///[`DS1302::get_mode`] reads the format from the hours register instead.
+        pub fn mode(&self) -> Mode {
+            self.mode
+        }
+
+        // Follow the hour format flag of every write reaching the hours register
+        fn track_mode(&mut self, bytes: &[u8]) {
+            let hours = if bytes[0] == Command::write(Register::HOURS.addr()).byte() {
+                bytes.get(1)
+            } else if bytes[0] == Command::burst_write().byte() {
+                bytes.get(3)
+            } else {
+                None
+            };
+            if let Some(&byte) = hours {
+                self.mode = Mode::of_hours_register(byte);
+            }
+        }
+
+        $($async)? fn read_reg(&mut self, reg: u8) -> Result<u8, $error> {
+            let mut bytes = [Command::read(reg).byte(), 0];
+            self.transfer(&mut bytes)$(.$await)??;
+            Ok(bytes[1])
+        }
+
+        // Clear the write protect bit if it is set or its state is not known yet
+        $($async)? fn unprotect(&mut self) -> Result<(), $error> {
+            let protected = match self.write_protect {
+                Some(wp) => wp,
+                None => (self.read_reg(Register::WP.addr())$(.$await)?? & WRITE_PROTECT_BIT) != 0,
+            };
+            if protected {
+                self.write_protect = None;
+                self.write(&[Command::write(Register::WP.addr()).byte(), 0])$(.$await)??;
+            }
+            self.write_protect = Some(false);
+            Ok(())
+        }
+
+        $($async)? fn write_reg(&mut self, reg: u8, byte: u8) -> Result<(), $error> {
+            self.unprotect()$(.$await)??;
+            let command = Command::write(reg);
+            if command == Command::write(Register::WP.addr()) {
+                self.write_protect = None;
+                self.write(&[command.byte(), byte])$(.$await)??;
+                self.write_protect = Some((byte & WRITE_PROTECT_BIT) != 0);
+                return Ok(());
+            }
+            self.write(&[command.byte(), byte])$(.$await)?
+        }
+
+        // Write several registers, not the write protect one, clearing the protection only once
+        $($async)? fn write_regs(&mut self, regs: &[(u8, u8)]) -> Result<(), $error> {
+            self.unprotect()$(.$await)??;
+            for &(reg, byte) in regs {
+                self.write(&[Command::write(reg).byte(), byte])$(.$await)??;
+            }
+            Ok(())
+        }
+
+        ///Read a register directly
+        ///
+        ///`addr` is the write address of the register from the datasheet, e.g. `0x80` for seconds,
+        ///the read bit is set by the driver. The raw byte is returned, flags and BCD included. The
+        ///typed getters should be preferred, this is meant for registers or bits the driver doesn't
+        ///cover. Addresses without bit 7 set are not commands and fail with
+        ///`Ds1302Error::Parameter`, and so do the burst commands (register 31, e.g. `0xBE`), which
+        ///transfer more than one byte.
+        pub $($async)? fn read_register(&mut self, addr: u8) -> Result<u8, $error> {
+            if addr & 0x80 == 0 || Command::write(addr).is_burst() {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.read_reg(addr)$(.$await)?
+        }
+
+        ///Write a register directly
+        ///
+        ///`addr` is the write address of the register from the datasheet and `value` is written as
+        ///is, no range or BCD conversion is done. Write protection is cleared first like for the
+        ///setters. Writing the write protect register itself changes the protection of the
+        ///following writes. Addresses without bit 7 set are not commands and fail with
+        ///`Ds1302Error::Parameter`, and so do the burst commands (register 31, e.g. `0xBE`).
+        pub $($async)? fn write_register(&mut self, addr: u8, value: u8) -> Result<(), $error> {
+            if addr & 0x80 == 0 || Command::write(addr).is_burst() {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.write_reg(addr, value)$(.$await)?
+        }
+
+        ///Read the raw timekeeping, write protect and trickle charger registers for diagnostics
+        ///
+        ///The order is the one of [`RegisterDump`](crate::RegisterDump), which can wrap the result
+        ///to print it.
+        pub $($async)? fn dump_registers(&mut self) -> Result<[u8; 9], $error> {
+            let mut bytes = [0_u8; 9];
+            bytes[0] = Command::burst_read().byte();
+            self.transfer(&mut bytes)$(.$await)??;
+            let mut dump = [0_u8; 9];
+            dump[..8].copy_from_slice(&bytes[1..9]);
+            dump[8] = self.read_reg(Register::TCS.addr())$(.$await)??;
+            Ok(dump)
+        }
+
+        ///Read the timekeeping registers, the trickle charger and the RAM as a backup of the chip
+        ///
+        ///Byte layout:
+        ///- 0..=6: seconds (with the clock halt flag), minutes, hours, date, month, day and year
+        ///  registers, in BCD like the clock burst
+        ///- 7: write protect register
+        ///- 8: trickle charger register
+        ///- 9..=39: the 31 bytes of the RAM
+        ///
+        ///The first 9 bytes are the ones of [`DS1302::dump_registers`].
+        pub $($async)? fn export_state(&mut self) -> Result<[u8; 40], $error> {
+            let mut state = [0_u8; 40];
+            state[..9].copy_from_slice(&self.dump_registers()$(.$await)??);
+            self.read_ram_burst(&mut state[9..])$(.$await)??;
+            Ok(state)
+        }
+
+        ///Write a backup made by [`DS1302::export_state`] back to the chip
+        ///
+        ///The clock and calendar are written in one burst, then the trickle charger and the RAM.
+        ///The write protection is cleared for the writes and enabled at the end if it was enabled
+        ///in the backup. The clock halt flag of the backup is kept, a halted clock stays halted.
+        pub $($async)? fn import_state(&mut self, state: &[u8; 40]) -> Result<(), $error> {
+            let mut clock_calendar = [0_u8; 7];
+            clock_calendar.copy_from_slice(&state[..7]);
+            // The write protect byte of the burst stays cleared for the following writes
+            self.set_clock_calendar_raw(&clock_calendar)$(.$await)??;
+            self.write_reg(Register::TCS.addr(), state[8])$(.$await)??;
+            self.write_ram_burst(&state[9..])$(.$await)??;
+            if state[7] & WRITE_PROTECT_BIT != 0 {
+                self.set_write_protect(true)$(.$await)??;
+            }
+            Ok(())
+        }
+
+        ///Return current information about seconds
+        ///
+        ///Fails with `Ds1302Error::InvalidData` if the register isn't valid BCD,
+        ///[`DS1302::get_seconds_raw`] returns it unchecked.
+        ///
+        ///Read several fields at once with [`DS1302::get_datetime`], see [`DS1302`].
+        pub $($async)? fn get_seconds(&mut self) -> Result<u8, $error> {
+            let byte = self.get_seconds_raw()$(.$await)??;
+            bcd_to_decimal_checked(byte & !CLOCK_HALT_FLAG).ok_or(Ds1302Error::InvalidData)
+        }
+
+        ///Return the raw seconds register, BCD with the clock halt flag in bit 7
+        pub $($async)? fn get_seconds_raw(&mut self) -> Result<u8, $error> {
+            self.read_reg(Register::SECONDS.addr())$(.$await)?
+        }
+
+        ///Wait until the seconds register changes and return the new seconds
+        ///
+        ///This aligns the caller to the tick of the chip, e.g. before taking a timestamp. The
+        ///register is polled back to back, at most 500 000 times. Fails with `Ds1302Error::Unknown`
+        ///if the oscillator is halted or the seconds don't change within the polls, and with
+        ///`Ds1302Error::InvalidData` if the new value isn't valid BCD.
+        pub $($async)? fn wait_for_second_change(&mut self) -> Result<u8, $error> {
+            let start = self.get_seconds_raw()$(.$await)??;
+            for _ in 0..SECOND_CHANGE_POLLS {
+                let byte = self.get_seconds_raw()$(.$await)??;
+                if (byte & CLOCK_HALT_FLAG) != 0 {
+                    return Err(Ds1302Error::Unknown);
+                }
+                if byte != start {
+                    return bcd_to_decimal_checked(byte).ok_or(Ds1302Error::InvalidData);
+                }
+            }
+            Err(Ds1302Error::Unknown)
+        }
+
+        ///Return current information about minutes
+        ///
+        ///Fails with `Ds1302Error::InvalidData` if the register isn't valid BCD,
+        ///[`DS1302::get_minutes_raw`] returns it unchecked.
+        ///
+        ///Read several fields at once with [`DS1302::get_datetime`], see [`DS1302`].
+        pub $($async)? fn get_minutes(&mut self) -> Result<u8, $error> {
+            let byte = self.get_minutes_raw()$(.$await)??;
+            bcd_to_decimal_checked(byte).ok_or(Ds1302Error::InvalidData)
+        }
+
+        ///Return the raw minutes register, BCD
+        pub $($async)? fn get_minutes_raw(&mut self) -> Result<u8, $error> {
+            self.read_reg(Register::MINUTES.addr())$(.$await)?
+        }
+
+        ///Return current information about hours
+        ///
+        ///Fails with `Ds1302Error::InvalidData` if the register isn't valid BCD.
+        ///
+        ///Read several fields at once with [`DS1302::get_datetime`], see [`DS1302`].
+        pub $($async)? fn get_hours(&mut self) -> Result<Hours, $error> {
+            let byte = self.get_hours_raw()$(.$await)??;
+            bcd_to_decimal_checked(hours_bcd(byte)).ok_or(Ds1302Error::InvalidData)?;
+            Ok(Hours::from(byte))
+        }
+
+        ///Return the raw hours register
+        ///
+        ///[`HOUR_12_BIT`](crate::HOUR_12_BIT) is set in the 12-hour format, then
+        ///[`HOUR_PM_BIT`](crate::HOUR_PM_BIT) marks PM and bits 4..=0 are the BCD hour 1..=12. In
+        ///the 24-hour format bits 5..=0 are the BCD hour 0..=23.
+        ///[`Hours::from`] decodes it and `u8::from(Hours)` encodes it back.
+        pub $($async)? fn get_hours_raw(&mut self) -> Result<u8, $error> {
+            self.read_reg(Register::HOURS.addr())$(.$await)?
+        }
+
+        ///Return the hour format the chip is in
+        pub $($async)? fn get_mode(&mut self) -> Result<Mode, $error> {
+            self.read_reg(Register::HOURS.addr())$(.$await)?
+                .map(Mode::of_hours_register)
+        }
+
+        ///Return current hour as 0..=23, whatever the hour format of the chip
+        pub $($async)? fn get_hour24(&mut self) -> Result<u8, $error> {
+            self.get_hours()$(.$await)?.map(|h| h.hour24())
+        }
+
+        ///Return current information about date
+        ///
+        ///Fails with `Ds1302Error::InvalidData` if the register isn't valid BCD,
+        ///[`DS1302::get_date_raw`] returns it unchecked.
+        ///
+        ///Read several fields at once with [`DS1302::get_datetime`], see [`DS1302`].
+        pub $($async)? fn get_date(&mut self) -> Result<u8, $error> {
+            let byte = self.get_date_raw()$(.$await)??;
+            bcd_to_decimal_checked(byte).ok_or(Ds1302Error::InvalidData)
+        }
+
+        ///Return the raw date register, BCD
+        pub $($async)? fn get_date_raw(&mut self) -> Result<u8, $error> {
+            self.read_reg(Register::DATE.addr())$(.$await)?
+        }
+
+        ///Return current information about month
+        ///
+        ///Fails with `Ds1302Error::InvalidData` if the register isn't valid BCD,
+        ///[`DS1302::get_month_raw`] returns it unchecked.
+        ///
+        ///Read several fields at once with [`DS1302::get_datetime`], see [`DS1302`].
+        pub $($async)? fn get_month(&mut self) -> Result<u8, $error> {
+            let byte = self.get_month_raw()$(.$await)??;
+            bcd_to_decimal_checked(byte).ok_or(Ds1302Error::InvalidData)
+        }
+
+        ///Return the raw month register, BCD
+        pub $($async)? fn get_month_raw(&mut self) -> Result<u8, $error> {
+            self.read_reg(Register::MONTH.addr())$(.$await)?
+        }
+
+        ///Return current information about year
+        ///
+        ///Fails with `Ds1302Error::InvalidData` if the register isn't valid BCD,
+        ///[`DS1302::get_year_raw`] returns it unchecked.
+        ///
+        ///Read several fields at once with [`DS1302::get_datetime`], see [`DS1302`].
+        pub $($async)? fn get_year(&mut self) -> Result<u16, $error> {
+            let byte = self.get_year_raw()$(.$await)??;
+            let year = bcd_to_decimal_checked(byte).ok_or(Ds1302Error::InvalidData)?;
+            Ok(2000 + u16::from(year))
+        }
+
+        ///Return the raw year register, BCD of the years since 2000
+        pub $($async)? fn get_year_raw(&mut self) -> Result<u8, $error> {
+            self.read_reg(Register::YEAR.addr())$(.$await)?
+        }
+
+        ///Return current information about day of the week, 1..=7
+        ///
+        ///Fails with `Ds1302Error::InvalidData` if the register isn't a valid BCD day 1..=7,
+        ///[`DS1302::get_day_raw`] returns it unchecked.
+        ///
+        ///Read several fields at once with [`DS1302::get_datetime`], see [`DS1302`].
+        pub $($async)? fn get_day(&mut self) -> Result<u8, $error> {
+            let byte = self.get_day_raw()$(.$await)??;
+            bcd_to_decimal_checked(byte)
+                .filter(|day| (1..=7).contains(day))
+                .ok_or(Ds1302Error::InvalidData)
+        }
+
+        ///Return the raw day register, BCD
+        pub $($async)? fn get_day_raw(&mut self) -> Result<u8, $error> {
+            self.read_reg(Register::DAY.addr())$(.$await)?
+        }
+
+        ///Return current day of the week, the chip must count 1 as Monday
+        ///
+        ///Fails with `Ds1302Error::InvalidData` if the day register is not 1..=7.
+        pub $($async)? fn get_weekday(&mut self) -> Result<Weekday, $error> {
+            let day = self.get_day()$(.$await)??;
+            Weekday::try_from(day).map_err(|_| Ds1302Error::InvalidData)
+        }
+
+        ///Return current information about hours, minutes and seconds
+        ///
+        ///The burst stops after the hours register, the calendar isn't read. Fails with
+        ///`Ds1302Error::InvalidData` if a register isn't valid BCD, the values aren't range
+        ///checked, see [`DS1302::get_datetime_checked`].
+        pub $($async)? fn get_clock(&mut self) -> Result<Clock, $error> {
+            let mut bytes = [0_u8; 4];
+            bytes[0] = Command::burst_read().byte();
+            self.transfer(&mut bytes)$(.$await)??;
+            if !clock_is_bcd(&bytes[1..4]) {
+                return Err(Ds1302Error::InvalidData);
+            }
+            Ok(decode_clock(&bytes[1..4]))
+        }
+
+        ///Return current information about date, day of the week, month and year
+        ///
+        ///Fails with `Ds1302Error::InvalidData` like [`DS1302::get_clock_calendar`].
+        pub $($async)? fn get_calendar(&mut self) -> Result<Calendar, $error> {
+            let (_, calendar) = self.get_clock_calendar()$(.$await)??;
+            Ok(calendar)
+        }
+
+        ///Return current information date and time
+        ///
+        ///Fails with `Ds1302Error::InvalidData` if a register isn't valid BCD, the values aren't
+        ///range checked, see [`DS1302::get_datetime_checked`].
+        pub $($async)? fn get_clock_calendar(&mut self) -> Result<(Clock, Calendar), $error> {
+            let mut bytes = [0_u8; 8];
+            bytes[0] = Command::burst_read().byte();
+            self.transfer(&mut bytes)$(.$await)??;
+            if !clock_calendar_is_bcd(&bytes[1..8]) {
+                return Err(Ds1302Error::InvalidData);
+            }
+            Ok(decode_burst(&bytes[1..8]))
+        }
+
+        ///Return current clock and calendar with the day of the week as [`Weekday`], in one burst
+        ///
+        ///The chip must count 1 as Monday. Fails with `Ds1302Error::InvalidData` if the day
+        ///register is not 1..=7.
+        pub $($async)? fn get_clock_calendar_weekday(
+            &mut self,
+        ) -> Result<(Clock, Calendar, Weekday), $error> {
+            let (clock, calendar) = self.get_clock_calendar()$(.$await)??;
+            let weekday = Weekday::try_from(calendar.day).map_err(|_| Ds1302Error::InvalidData)?;
+            Ok((clock, calendar, weekday))
+        }
+
+        ///Return current date and time as one value
+        ///
+        ///Fails with `Ds1302Error::InvalidData` like [`DS1302::get_clock_calendar`].
+        pub $($async)? fn get_datetime(&mut self) -> Result<DateTime, $error> {
+            let (clock, calendar) = self.get_clock_calendar()$(.$await)??;
+            Ok(DateTime { clock, calendar })
+        }
+
+        ///Return a coherent snapshot of the clock and calendar
+        ///
+        ///Same as [`DS1302::get_datetime`], named for the callers combining the single-field
+        ///getters, see [`DS1302`].
+        pub $($async)? fn get_time_consistent(&mut self) -> Result<DateTime, $error> {
+            self.get_datetime()$(.$await)?
+        }
+
+        ///Set seconds to defined value, the running state of the oscillator is kept
+        pub $($async)? fn set_seconds(&mut self, seconds: u8) -> Result<(), $error> {
+            if seconds > 59 {
+                return Err(Ds1302Error::Parameter);
+            }
+            let ch = self.read_reg(Register::SECONDS.addr())$(.$await)?? & CLOCK_HALT_FLAG;
+            self.write_reg(Register::SECONDS.addr(), decimal_to_bcd(seconds) | ch)$(.$await)?
+        }
+
+        ///Set seconds to defined value and stop the oscillator
+        pub $($async)? fn set_seconds_and_halt(&mut self, seconds: u8) -> Result<(), $error> {
+            if seconds > 59 {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.write_reg(
+                Register::SECONDS.addr(),
+                decimal_to_bcd(seconds) | CLOCK_HALT_FLAG,
+            )$(.$await)?
+        }
+
+        ///Set minutes to defined value
+        pub $($async)? fn set_minutes(&mut self, minutes: u8) -> Result<(), $error> {
+            if minutes > 59 {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.write_reg(Register::MINUTES.addr(), decimal_to_bcd(minutes))$(.$await)?
+        }
+
+        ///Set hours to defined value
+        pub $($async)? fn set_hours(&mut self, hours: Hours) -> Result<(), $error> {
+            if !hours.is_valid() {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.write_reg(Register::HOURS.addr(), hours.into())$(.$await)?
+        }
+
+        ///Set hour from 0..=23, it is written in the hour format the chip is in
+        pub $($async)? fn set_hour24(&mut self, hour: u8) -> Result<(), $error> {
+            if hour > 23 {
+                return Err(Ds1302Error::Parameter);
+            }
+            let hours = if self.read_reg(Register::HOURS.addr())$(.$await)?? & HOUR_12_BIT != 0 {
+                Hours::Hour24(hour).convert()
+            } else {
+                Hours::Hour24(hour)
+            };
+            self.set_hours(hours)$(.$await)?
+        }
+
+        ///Set date to defined value
+        ///
+        ///The date is checked against the month and year stored in the chip, set those first.
+        pub $($async)? fn set_date(&mut self, date: u8) -> Result<(), $error> {
+            // No month has more days, nothing needs to be read to reject these
+            if !(1..=31).contains(&date) {
+                return Err(Ds1302Error::Parameter);
+            }
+            let month = bcd_to_decimal_checked(self.get_month_raw()$(.$await)??);
+            // An invalid year is taken as a leap year, so that February 29 can still be set
+            let year = bcd_to_decimal_checked(self.get_year_raw()$(.$await)??)
+                .map_or(2000, |y| 2000 + u16::from(y));
+            // A month register out of range can't be checked against, the date is only kept 1..=31
+            let days = month
+                .and_then(|month| days_in_month(year, month))
+                .unwrap_or(31);
+            if !(1..=days).contains(&date) {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.write_reg(Register::DATE.addr(), decimal_to_bcd(date))$(.$await)?
+        }
+
+        ///Set month to defined value
+        pub $($async)? fn set_month(&mut self, month: u8) -> Result<(), $error> {
+            if !(1..=12).contains(&month) {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.write_reg(Register::MONTH.addr(), decimal_to_bcd(month))$(.$await)?
+        }
+
+        ///Set day of the week to defined value
+        pub $($async)? fn set_day(&mut self, day: u8) -> Result<(), $error> {
+            if !(1..=7).contains(&day) {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.write_reg(Register::DAY.addr(), decimal_to_bcd(day))$(.$await)?
+        }
+
+        ///Set day of the week, 1 is Monday
+        pub $($async)? fn set_weekday(&mut self, day: Weekday) -> Result<(), $error> {
+            self.set_day(day.into())$(.$await)?
+        }
+
+        ///Set year to defined value, 2000..=2099
+        pub $($async)? fn set_year(&mut self, year: u16) -> Result<(), $error> {
+            if !(2000..=2099).contains(&year) {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.write_reg(Register::YEAR.addr(), decimal_to_bcd((year - 2000) as u8))$(.$await)?
+        }
+
+        ///Set clock to defined values, the running state of the oscillator is kept
+        ///
+        ///Fails with `Ds1302Error::Parameter` before writing anything if a value is out of range.
+        pub $($async)? fn set_clock(&mut self, clock: Clock) -> Result<(), $error> {
+            if !clock.is_valid() {
+                return Err(Ds1302Error::Parameter);
+            }
+            let ch = self.read_reg(Register::SECONDS.addr())$(.$await)?? & CLOCK_HALT_FLAG;
+            //Not burst mode, because it changes the calendar registers
+            self.write_regs(&[
+                (Register::HOURS.addr(), clock.hours.into()),
+                (Register::MINUTES.addr(), decimal_to_bcd(clock.minutes)),
+                (Register::SECONDS.addr(), decimal_to_bcd(clock.seconds) | ch),
+            ])$(.$await)?
+        }
+
+        ///Set calendar to defined values
+        ///
+        ///Fails with `Ds1302Error::Parameter` before writing anything if the date doesn't exist or
+        ///the year is outside of 2000..=2099.
+        ///The registers are written one by one and the write protection is cleared only once.
+        ///Unlike [`DS1302::set_clock_calendar`] the seconds, minutes and hours are left untouched,
+        ///the chip can't burst-write the calendar alone.
+        pub $($async)? fn set_calendar(&mut self, calendar: Calendar) -> Result<(), $error> {
+            if !calendar.is_valid() || !(2000..=2099).contains(&calendar.year) {
+                return Err(Ds1302Error::Parameter);
+            }
+            //Not burst mode, because it changes the clock registers
+            self.write_regs(&[
+                (
+                    Register::YEAR.addr(),
+                    decimal_to_bcd((calendar.year - 2000) as u8),
+                ),
+                (Register::MONTH.addr(), decimal_to_bcd(calendar.month)),
+                (Register::DATE.addr(), decimal_to_bcd(calendar.date)),
+                (Register::DAY.addr(), decimal_to_bcd(calendar.day)),
+            ])$(.$await)?
+        }
+
+        ///Set calendar to defined values, the day of the week is computed from the date
+        ///
+        ///`calendar.day` is ignored and replaced by [`weekday`], 1 is Monday and 7 is Sunday.
+        pub $($async)? fn set_calendar_autoday(
+            &mut self,
+            calendar: Calendar,
+        ) -> Result<(), $error> {
+            let day = weekday(calendar.year, calendar.month, calendar.date)
+                .ok_or(Ds1302Error::Parameter)?;
+            self.set_calendar(Calendar { day, ..calendar })$(.$await)?
+        }
+
+        ///Set calendar to defined values in one burst, keeping the clock
+        ///
+        ///The clock bytes are read in one burst and written back with the new calendar, the
+        ///halt flag and the hour format included. `calendar.day` is written as is. Fails with
+        ///`Ds1302Error::Parameter` before writing anything if the date doesn't exist or the year is
+        ///outside of 2000..=2099. A second that passes between the read and the write is lost, use
+        ///[`DS1302::set_calendar`] if the clock must not be written at all.
+        pub $($async)? fn set_calendar_burst(&mut self, calendar: Calendar) -> Result<(), $error> {
+            if !calendar.is_valid() || !(2000..=2099).contains(&calendar.year) {
+                return Err(Ds1302Error::Parameter);
+            }
+            let mut bytes = [0_u8; 4];
+            bytes[0] = Command::burst_read().byte();
+            self.transfer(&mut bytes)$(.$await)??;
+            let bytes = splice_calendar(&bytes[1..4], calendar);
+            self.unprotect()$(.$await)??;
+            self.write(&bytes)$(.$await)?
+        }
+
+        ///Return current date and time, checked with [`DateTime::is_valid`]
+        ///
+        ///Fails with `Ds1302Error::InvalidData` if the chip doesn't hold a valid date and time.
+        ///All registers are read in one burst like [`DS1302::get_datetime`], every field must be
+        ///valid BCD and in range and the date must exist in its month, which catches the garbage
+        ///left after a loss of the backup supply.
+        pub $($async)? fn get_datetime_checked(&mut self) -> Result<DateTime, $error> {
+            let mut bytes = [0_u8; 8];
+            bytes[0] = Command::burst_read().byte();
+            self.transfer(&mut bytes)$(.$await)??;
+            // A nibble above 9 would still decode to a plausible value
+            if !clock_calendar_is_bcd(&bytes[1..8]) {
+                return Err(Ds1302Error::InvalidData);
+            }
+            let (clock, calendar) = decode_burst(&bytes[1..8]);
+            let datetime = DateTime { clock, calendar };
+            if !datetime.is_valid() {
+                return Err(Ds1302Error::InvalidData);
+            }
+            Ok(datetime)
+        }
+
+        ///Same as [`DS1302::get_datetime_checked`], which is the name to use
+        #[deprecated(note = "use `get_datetime_checked`, this is the same method")]
+        pub $($async)? fn get_datetime_validated(&mut self) -> Result<DateTime, $error> {
+            self.get_datetime_checked()$(.$await)?
+        }
+
+        ///Set clock and calendar to defined values in one burst, the oscillator is started
+        ///
+        ///Fails with `Ds1302Error::Parameter` before writing anything if the values aren't valid,
+        ///see [`DateTime::is_valid`].
+        pub $($async)? fn set_clock_calendar(
+            &mut self,
+            clock: Clock,
+            calendar: Calendar,
+        ) -> Result<(), $error> {
+            if !(DateTime { clock, calendar }).is_valid() {
+                return Err(Ds1302Error::Parameter);
+            }
+            //Writing in burst mode, it changes all the clock and calendar registers
+            let bytes = encode_clock_calendar(clock, calendar);
+            self.unprotect()$(.$await)??;
+            self.write(&bytes)$(.$await)?
+        }
+
+        ///Write the clock and calendar registers in one burst from raw register values
+        ///
+        ///`bytes` are the seconds, minutes, hours, date, month, day and year registers in this
+        ///order, the order of [`DS1302::dump_registers`]. They are written as is: BCD, with the
+        ///clock halt flag in bit 7 of the seconds and the hour format flags of the hours, no range
+        ///check is done. The write protect byte of the burst is cleared.
+        pub $($async)? fn set_clock_calendar_raw(&mut self, bytes: &[u8; 7]) -> Result<(), $error> {
+            let mut burst = [0_u8; 9];
+            burst[0] = Command::burst_write().byte();
+            burst[1..8].copy_from_slice(bytes);
+            self.unprotect()$(.$await)??;
+            self.write(&burst)$(.$await)?
+        }
+
+        ///Set date and time in one burst, see [`DS1302::set_clock_calendar`]
+        pub $($async)? fn set_datetime(&mut self, datetime: DateTime) -> Result<(), $error> {
+            self.set_clock_calendar(datetime.clock, datetime.calendar)$(.$await)?
+        }
+
+        ///Move the date and time of the chip `secs` seconds forward, see [`DateTime::add_seconds`]
+        ///
+        ///The date and time are read and written back in one burst each, which starts the
+        ///oscillator. Fails with `Ds1302Error::InvalidData` if the chip doesn't hold a valid date
+        ///and time.
+        pub $($async)? fn advance(&mut self, secs: u32) -> Result<(), $error> {
+            let mut datetime = self.get_datetime_checked()$(.$await)??;
+            datetime
+                .add_seconds(secs)
+                .map_err(|_| Ds1302Error::InvalidData)?;
+            self.set_datetime(datetime)$(.$await)?
+        }
+
+        ///Set clock and calendar in one burst, with the day of the week computed by [`weekday`]
+        ///
+        ///Fails with `Ds1302Error::Parameter` before writing anything if the clock is out of range,
+        ///the date doesn't exist or the year is outside of 2000..=2099.
+        pub $($async)? fn set_datetime_autoday(
+            &mut self,
+            clock: Clock,
+            year: u16,
+            month: u8,
+            date: u8,
+        ) -> Result<(), $error> {
+            if !clock.is_valid() {
+                return Err(Ds1302Error::Parameter);
+            }
+            let day = weekday(year, month, date).ok_or(Ds1302Error::Parameter)?;
+            let calendar = Calendar::new(day, date, month, year).map_err(Ds1302Error::into_driver)?;
+            self.set_clock_calendar(clock, calendar)$(.$await)?
+        }
+
+        ///Set date and time from a Unix timestamp (seconds since 1970-01-01 00:00:00 UTC)
+        ///
+        ///The hour format of the chip is kept and the day of the week is set by [`weekday`].
+        ///Timestamps outside of the years 2000..=2099 fail with `Ds1302Error::Parameter`.
+        pub $($async)? fn set_from_unix(&mut self, secs: u64) -> Result<(), $error> {
+            let mut datetime = unix_to_datetime(secs).ok_or(Ds1302Error::Parameter)?;
+            if self.read_reg(Register::HOURS.addr())$(.$await)?? & HOUR_12_BIT != 0 {
+                datetime.clock.hours = datetime.clock.hours.convert();
+            }
+            self.set_datetime(datetime)$(.$await)?
+        }
+
+        ///Return current date and time as a Unix timestamp, the chip is assumed to run on UTC
+        ///
+        ///Fails with `Ds1302Error::InvalidData` if the chip doesn't hold a valid date and time.
+        pub $($async)? fn get_unix(&mut self) -> Result<u64, $error> {
+            let datetime = self.get_datetime()$(.$await)??;
+            datetime_to_unix(&datetime).ok_or(Ds1302Error::InvalidData)
+        }
+
+        ///Return the seconds left until `target`, negative once it has passed
+        ///
+        ///Meant for alarms kept in software, the chip has none. Both dates and times are compared
+        ///as Unix timestamps, so 12-hour and 24-hour clocks can be mixed and the day of the week is
+        ///ignored. Fails with `Ds1302Error::Parameter` if `target` isn't a valid date and time, and
+        ///with `Ds1302Error::InvalidData` if the chip doesn't hold one, see
+        ///[`DS1302::get_datetime_checked`].
+        pub $($async)? fn seconds_until(&mut self, target: &DateTime) -> Result<i64, $error> {
+            let target = datetime_to_unix(target).ok_or(Ds1302Error::Parameter)?;
+            let now = self.get_datetime_checked()$(.$await)??;
+            let now = datetime_to_unix(&now).ok_or(Ds1302Error::InvalidData)?;
+            Ok(target as i64 - now as i64)
+        }
+
+        ///Return whether `target` is reached, see [`DS1302::seconds_until`]
+        pub $($async)? fn is_past(&mut self, target: &DateTime) -> Result<bool, $error> {
+            Ok(self.seconds_until(target)$(.$await)?? <= 0)
+        }
+
+        ///Switch between 12-hour (AM/PM) and 24-hour mode
+        ///
+        ///An hours register that doesn't hold a valid hour, e.g. after a loss of the backup supply,
+        ///is reset to midnight in the new format instead of failing, so that the constructors still
+        ///succeed.
+        pub $($async)? fn set_clock_mode(&mut self, mode: Mode) -> Result<(), $error> {
+            let byte = self.read_reg(Register::HOURS.addr())$(.$await)??;
+            // Nothing to write if the chip is already in this format
+            if Mode::of_hours_register(byte) != mode {
+                self.set_hours(hours_in_mode(byte, mode))$(.$await)??;
+            }
+            self.mode = mode;
+            Ok(())
+        }
+
+        /// Stop the oscillator by setting the clock halt flag. The stored seconds value is
+        /// preserved.
+        pub $($async)? fn halt(&mut self) -> Result<(), $error> {
+            let byte = self.read_reg(Register::SECONDS.addr())$(.$await)??;
+            self.write_reg(Register::SECONDS.addr(), byte | CLOCK_HALT_FLAG)$(.$await)?
+        }
+
+        /// Restart the oscillator by clearing the clock halt flag. The stored seconds value is
+        /// preserved.
+        pub $($async)? fn resume(&mut self) -> Result<(), $error> {
+            let byte = self.read_reg(Register::SECONDS.addr())$(.$await)??;
+            self.write_reg(Register::SECONDS.addr(), byte & !CLOCK_HALT_FLAG)$(.$await)?
+        }
+
+        /// Whether the oscillator is running (clock halt flag is cleared).
+        pub $($async)? fn is_running(&mut self) -> Result<bool, $error> {
+            let byte = self.read_reg(Register::SECONDS.addr())$(.$await)??;
+            Ok((byte & CLOCK_HALT_FLAG) == 0)
+        }
+
+        /// Enable or disable the write protection of the chip.
+        /// Note that every `set_*` method clears the write protection bit before writing,
+        /// so enabling it only blocks writes made outside of this driver.
+        pub $($async)? fn set_write_protect(&mut self, enabled: bool) -> Result<(), $error> {
+            let byte = if enabled { WRITE_PROTECT_BIT } else { 0 };
+            self.write_protect = None;
+            self.write(&[Command::write(Register::WP.addr()).byte(), byte])$(.$await)??;
+            self.write_protect = Some(enabled);
+            Ok(())
+        }
+
+        /// Whether the write protection of the chip is enabled.
+        pub $($async)? fn is_write_protected(&mut self) -> Result<bool, $error> {
+            let wp = (self.read_reg(Register::WP.addr())$(.$await)?? & WRITE_PROTECT_BIT) != 0;
+            self.write_protect = Some(wp);
+            Ok(wp)
+        }
+
+        /// Run `f` with the write protection cleared once, then restore it if it was enabled.
+        /// The writes of `f` don't touch the write protect register again.
+        ///
+        /// The write protection is restored even if `f` fails, the error of `f` is returned first.
+        pub $($async)? fn with_writes_enabled<R, F>(&mut self, f: F) -> Result<R, $error>
+        where
+            F: $fn_once(&mut Self) -> Result<R, $error>,
+        {
+            let protected = match self.write_protect {
+                Some(wp) => wp,
+                None => self.is_write_protected()$(.$await)??,
+            };
+            self.unprotect()$(.$await)??;
+            let result = f(self)$(.$await)?;
+            if protected {
+                let restored = self.set_write_protect(true)$(.$await)?;
+                let value = result?;
+                restored?;
+                return Ok(value);
+            }
+            result
+        }
+
+        /// Read the raw write protect register, only bit 7 is defined and the others read as 0.
+        /// This is a single read, no write is issued unlike the setters clearing the protection.
+        pub $($async)? fn write_protect_raw(&mut self) -> Result<u8, $error> {
+            let byte = self.read_reg(Register::WP.addr())$(.$await)??;
+            self.write_protect = Some((byte & WRITE_PROTECT_BIT) != 0);
+            Ok(byte)
+        }
+
+        /// Enable trickle-charge.
+        /// Ds (diode drop voltage 0.7 or 1.4)
+        /// Rs (2k or 4k or 8k)
+        /// The maximum current = (Vcc - Ds) / Rs, see
+        /// [`tc_max_current_ua`](crate::tc_max_current_ua).
+        pub $($async)? fn tc_enable(&mut self, ds: Ds, rs: Rs) -> Result<(), $error> {
+            self.write_reg(Register::TCS.addr(), TrickleCharger::enable(ds, rs))$(.$await)?
+        }
+
+        /// Enable trickle-charge like [`DS1302::tc_enable`], after checking the configuration
+        /// against the supply voltage `vcc_mv` in millivolts.
+        /// Fails with `Ds1302Error::Parameter` before writing anything if `vcc_mv` doesn't exceed
+        /// the diode drop, nothing would be charged, or if the maximum current of
+        /// [`tc_max_current_ua`](crate::tc_max_current_ua) is above
+        /// [`TC_MAX_SAFE_CURRENT_UA`](crate::TC_MAX_SAFE_CURRENT_UA).
+        pub $($async)? fn tc_enable_checked(
+            &mut self,
+            ds: Ds,
+            rs: Rs,
+            vcc_mv: u16,
+        ) -> Result<(), $error> {
+            if !tc_is_safe(ds, rs, vcc_mv) {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.tc_enable(ds, rs)$(.$await)?
+        }
+
+        /// Disable trickle-charge.
+        pub $($async)? fn tc_disable(&mut self) -> Result<(), $error> {
+            self.write_reg(Register::TCS.addr(), TrickleCharger::disable())$(.$await)?
+        }
+
+        /// Get the configuration of the trickle-charge register.
+        #[allow(clippy::type_complexity)]
+        pub $($async)? fn tc_get(&mut self) -> Result<(bool, Option<Ds>, Option<Rs>), $error> {
+            let v = self.read_reg(Register::TCS.addr())$(.$await)??;
+            Ok(TrickleCharger::from(v).get())
+        }
+
+        /// Read the raw trickle charger register, see [`tc_describe`](crate::tc_describe).
+        pub $($async)? fn tc_raw(&mut self) -> Result<u8, $error> {
+            self.read_reg(Register::TCS.addr())$(.$await)?
+        }
+
+        /// Get the diode and resistor of the trickle charger, `None` if it is disabled.
+        pub $($async)? fn tc_config(&mut self) -> Result<Option<(Ds, Rs)>, $error> {
+            let v = self.read_reg(Register::TCS.addr())$(.$await)??;
+            match TrickleCharger::from(v).get() {
+                (true, Some(ds), Some(rs)) => Ok(Some((ds, rs))),
+                _ => Ok(None),
+            }
+        }
+
+        /// Whether to enable charging.
+        pub $($async)? fn tc_is_enabled(&mut self) -> Result<bool, $error> {
+            let v = self.read_reg(Register::TCS.addr())$(.$await)??;
+            Ok(TrickleCharger::from(v).is_enabled())
+        }
+
+        /// Read DS1302 internal RAM. The static RAM is 31 x 8 bytes, index 0..=30.
+        pub $($async)? fn read_ram(&mut self, index: u8) -> Result<u8, $error> {
+            // Index 30 is register 0xFC, 0xFE would already be the RAM burst command
+            if usize::from(index) >= RAM_SIZE {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.read_reg(Register::RAM.addr() + index * 2)$(.$await)?
+        }
+
+        /// Write DS1302 internal RAM. The static RAM is 31 x 8 bytes, index 0..=30.
+        pub $($async)? fn write_ram(&mut self, index: u8, value: u8) -> Result<(), $error> {
+            // Index 30 is register 0xFC, 0xFE would already be the RAM burst command
+            if usize::from(index) >= RAM_SIZE {
+                return Err(Ds1302Error::Parameter);
+            }
+            self.write_reg(Register::RAM.addr() + index * 2, value)$(.$await)?
+        }
+
+        /// Write DS1302 internal RAM and read the byte back, index 0..=30.
+        /// Fails with `Ds1302Error::Verify` if the chip didn't store `value`, e.g. near a brownout.
+        pub $($async)? fn write_ram_verified(
+            &mut self,
+            index: u8,
+            value: u8,
+        ) -> Result<(), $error> {
+            self.write_ram(index, value)$(.$await)??;
+            if self.read_ram(index)$(.$await)?? != value {
+                return Err(Ds1302Error::Verify);
+            }
+            Ok(())
+        }
+
+        /// Read DS1302 internal RAM from index `start`, one byte per transaction.
+        /// `start + buf.len()` cannot exceed 31, otherwise `Ds1302Error::Parameter` is returned.
+        pub $($async)? fn read_ram_range(
+            &mut self,
+            start: u8,
+            buf: &mut [u8],
+        ) -> Result<(), $error> {
+            if usize::from(start) + buf.len() > RAM_SIZE {
+                return Err(Ds1302Error::Parameter);
+            }
+            // The RAM burst always starts at index 0
+            for (index, byte) in (start..).zip(buf.iter_mut()) {
+                *byte = self.read_ram(index)$(.$await)??;
+            }
+            Ok(())
+        }
+
+        /// Write DS1302 internal RAM from index `start`, one byte per transaction.
+        /// `start + buf.len()` cannot exceed 31, otherwise `Ds1302Error::Parameter` is returned.
+        pub $($async)? fn write_ram_range(&mut self, start: u8, buf: &[u8]) -> Result<(), $error> {
+            if usize::from(start) + buf.len() > RAM_SIZE {
+                return Err(Ds1302Error::Parameter);
+            }
+            for (index, byte) in (start..).zip(buf.iter()) {
+                self.write_ram(index, *byte)$(.$await)??;
+            }
+            Ok(())
+        }
+
+        /// Read a plain data value stored by `write_ram_struct` from the RAM bytes at `offset`,
+        /// one byte per transaction. `offset + size_of::<T>()` cannot exceed 31,
+        /// otherwise `Ds1302Error::Parameter` is returned.
+        #[cfg(feature = "bytemuck")]
+        pub $($async)? fn read_ram_struct<T: bytemuck::Pod>(
+            &mut self,
+            offset: u8,
+        ) -> Result<T, $error> {
+            let mut value = T::zeroed();
+            self.read_ram_range(offset, bytemuck::bytes_of_mut(&mut value))$(.$await)??;
+            Ok(value)
+        }
+
+        /// Write the bytes of a plain data value to the RAM from `offset`, one byte per
+        /// transaction. `offset + size_of::<T>()` cannot exceed 31, otherwise
+        /// `Ds1302Error::Parameter` is returned.
+        ///
+        /// The bytes are stored in the memory layout of `T`, so it should be `#[repr(C)]` and the
+        /// same build should read it back.
+        #[cfg(feature = "bytemuck")]
+        pub $($async)? fn write_ram_struct<T: bytemuck::Pod>(
+            &mut self,
+            offset: u8,
+            value: &T,
+        ) -> Result<(), $error> {
+            self.write_ram_range(offset, bytemuck::bytes_of(value))$(.$await)?
+        }
+
+        /// Read DS1302 internal RAM burst mode. Start at 0 index.
+        /// The length is determined by the buf, a buf longer than 31 fails with
+        /// `Ds1302Error::Parameter`.
+        pub $($async)? fn read_ram_burst(&mut self, buf: &mut [u8]) -> Result<(), $error> {
+            if buf.len() > RAM_SIZE {
+                return Err(Ds1302Error::Parameter);
+            }
+            let mut bytes = [0_u8; RAM_SIZE + 1];
+            bytes[0] = Command::ram_burst_read().byte();
+            self.transfer(&mut bytes[..(buf.len() + 1)])$(.$await)??;
+            buf.copy_from_slice(&bytes[1..(buf.len() + 1)]);
+            Ok(())
+        }
+
+        /// Write DS1302 internal RAM burst mode. Start at 0 index.
+        /// The length is determined by the buf, a buf longer than 31 fails with
+        /// `Ds1302Error::Parameter`. Returns the number of bytes written.
+        pub $($async)? fn write_ram_burst(&mut self, buf: &[u8]) -> Result<usize, $error> {
+            if buf.len() > RAM_SIZE {
+                return Err(Ds1302Error::Parameter);
+            }
+            let mut bytes = [0_u8; RAM_SIZE + 1];
+            bytes[0] = Command::ram_burst_write().byte();
+            bytes[1..(buf.len() + 1)].copy_from_slice(buf);
+
+            self.unprotect()$(.$await)??;
+            self.write(&bytes[..(buf.len() + 1)])$(.$await)??;
+            Ok(buf.len())
+        }
+
+        /// Write DS1302 internal RAM in burst mode like `write_ram_burst` and read it back in a
+        /// second burst. Fails with `Ds1302Error::Verify` if a byte wasn't stored.
+        pub $($async)? fn write_ram_burst_verified(&mut self, buf: &[u8]) -> Result<usize, $error> {
+            let len = self.write_ram_burst(buf)$(.$await)??;
+            let mut read = [0_u8; RAM_SIZE];
+            self.read_ram_burst(&mut read[..len])$(.$await)??;
+            if read[..len] != *buf {
+                return Err(Ds1302Error::Verify);
+            }
+            Ok(len)
+        }
+
+        /// Read the whole DS1302 internal RAM in burst mode
+        pub $($async)? fn read_ram_all(&mut self) -> Result<[u8; RAM_SIZE], $error> {
+            let mut buf = [0_u8; RAM_SIZE];
+            self.read_ram_burst(&mut buf)$(.$await)??;
+            Ok(buf)
+        }
+
+        /// Write the whole DS1302 internal RAM in burst mode
+        pub $($async)? fn write_ram_all(&mut self, data: &[u8; RAM_SIZE]) -> Result<(), $error> {
+            self.write_ram_burst(data)$(.$await)??;
+            Ok(())
+        }
+
+        /// Set all 31 RAM bytes to `fill` in one burst, e.g. before storing fresh state.
+        /// The write protection is cleared like for the other writes.
+        pub $($async)? fn clear_ram(&mut self, fill: u8) -> Result<(), $error> {
+            self.write_ram_all(&[fill; RAM_SIZE])$(.$await)?
+        }
+
+        /// Set all 31 RAM bytes to 0, see [`DS1302::clear_ram`]
+        pub $($async)? fn zero_ram(&mut self) -> Result<(), $error> {
+            self.clear_ram(0)$(.$await)?
+        }
+
+        /// Iterate over the 31 RAM bytes, from index 0
+        ///
+        /// The whole RAM is read in one burst up front, so iterating doesn't touch the bus and
+        /// the bytes are a snapshot of the RAM at the time of the call.
+        pub $($async)? fn ram_iter(
+            &mut self,
+        ) -> Result<core::array::IntoIter<u8, RAM_SIZE>, $error> {
+            let ram = self.read_ram_all()$(.$await)??;
+            Ok(IntoIterator::into_iter(ram))
+        }
+
+        /// Read the RAM in one burst, let `f` change a copy of it and write the changes back in one
+        /// burst, instead of one transaction per byte.
+        ///
+        /// If `f` fails nothing is written and its error is returned. Nothing is written either
+        /// when `f` doesn't change any byte.
+        pub $($async)? fn ram_transaction<R, F>(
+            &mut self,
+            f: F,
+        ) -> Result<R, $error>
+        where
+            F: FnOnce(&mut RamBuffer) -> Result<R, Ds1302Error>,
+        {
+            let mut ram = RamBuffer::new(self.read_ram_all()$(.$await)??);
+            let value = f(&mut ram).map_err(Ds1302Error::into_driver)?;
+            if !ram.dirty().is_empty() {
+                self.write_ram_burst(ram.dirty())$(.$await)??;
+            }
+            Ok(value)
+        }
+
+        /// Store the [`crc8`] of the RAM bytes in `region` in the byte after it, returns the CRC.
+        /// `region.end` is the index of the CRC byte and must be 0..=30,
+        /// otherwise `Ds1302Error::Parameter` is returned.
+        ///
+        /// Call it after writing the region, [`DS1302::verify_ram`] then detects a corrupted byte.
+        pub $($async)? fn update_ram_crc(&mut self, region: Range<u8>) -> Result<u8, $error> {
+            if !crc_region_is_valid(&region) {
+                return Err(Ds1302Error::Parameter);
+            }
+            let ram = self.read_ram_all()$(.$await)??;
+            let crc = crc8(&ram[usize::from(region.start)..usize::from(region.end)]);
+            self.write_ram(region.end, crc)$(.$await)??;
+            Ok(crc)
+        }
+
+        /// Whether the RAM bytes in `region` match the CRC stored after them by
+        /// [`DS1302::update_ram_crc`]. The whole RAM is read in one burst.
+        pub $($async)? fn verify_ram(&mut self, region: Range<u8>) -> Result<bool, $error> {
+            if !crc_region_is_valid(&region) {
+                return Err(Ds1302Error::Parameter);
+            }
+            let ram = self.read_ram_all()$(.$await)??;
+            let crc = crc8(&ram[usize::from(region.start)..usize::from(region.end)]);
+            Ok(crc == ram[usize::from(region.end)])
+        }
+
+        /// Tell a cold boot from a warm boot with a marker in the RAM bytes `offset..offset + 4`.
+        /// Returns `true` if they already hold `magic` (little-endian), otherwise `magic` is
+        /// written and `false` is returned. The bytes must fit into the 31 bytes of the RAM,
+        /// otherwise `Ds1302Error::Parameter` is returned.
+        ///
+        /// A marker that is lost with the backup supply means the clock has to be set again.
+        pub $($async)? fn check_and_mark_initialized(
+            &mut self,
+            magic: u32,
+            offset: u8,
+        ) -> Result<bool, $error> {
+            if usize::from(offset) + 4 > RAM_SIZE {
+                return Err(Ds1302Error::Parameter);
+            }
+            let mut bytes = [0_u8; 4];
+            self.read_ram_range(offset, &mut bytes)$(.$await)??;
+            if u32::from_le_bytes(bytes) == magic {
+                return Ok(true);
+            }
+            self.write_ram_range(offset, &magic.to_le_bytes())$(.$await)??;
+            Ok(false)
+        }
+    };
+}
+
+pub(crate) use driver_methods;
+
+#[cfg(all(test, feature = "async"))]
+mod tests {
+    use crate::mock::{self, asynch::block_on};
+    use crate::{Calendar, Clock, DateTime, Ds1302Error, Hours, Mode};
+    use embedded_hal_mock::{eh0, eh1};
+    use std::vec::Vec;
+
+    enum Op<'a> {
+        Read(u8, u8),
+        Write(u8, u8),
+        Burst(&'a [u8]),
+        WriteBytes(&'a [u8]),
+    }
+
+    fn rejected<T, S, P, C>(result: Result<T, Ds1302Error<S, P, C>>) -> bool {
+        matches!(result, Err(Ds1302Error::Parameter))
+    }
+
+    // A blocking and an async driver both expecting the transactions `ops`
+    fn both(
+        ops: &[Op],
+    ) -> (
+        mock::Rtc,
+        eh0::spi::Mock,
+        mock::asynch::Rtc,
+        eh1::spi::Mock<u8>,
+    ) {
+        let blocking: Vec<_> = ops
+            .iter()
+            .map(|op| match *op {
+                Op::Read(addr, value) => mock::read(addr, value),
+                Op::Write(addr, value) => mock::write(addr, value),
+                Op::Burst(regs) => mock::burst_read(regs),
+                Op::WriteBytes(bytes) => mock::write_bytes(bytes),
+            })
+            .collect();
+        let asynch: Vec<_> = ops
+            .iter()
+            .map(|op| match *op {
+                Op::Read(addr, value) => mock::asynch::read(addr, value),
+                Op::Write(addr, value) => mock::asynch::write(addr, value),
+                Op::Burst(regs) => mock::asynch::burst_read(regs),
+                Op::WriteBytes(bytes) => mock::asynch::write_bytes(bytes),
+            })
+            .collect();
+        let (rtc, spi) = mock::rtc(&blocking);
+        let (rtc_async, spi_async) = mock::asynch::rtc(&asynch);
+        (rtc, spi, rtc_async, spi_async)
+    }
+
+    #[test]
+    fn both_drivers_issue_the_same_writes() {
+        let (mut rtc, mut spi, mut rtc_async, mut spi_async) = both(&[
+            Op::Read(0x8E, 0x80),
+            Op::Write(0x8E, 0x00),
+            Op::Write(0x82, 0x34),
+            Op::Read(0x80, 0x12),
+            Op::Write(0x80, 0x56),
+            Op::Write(0x84, 0xA1),
+        ]);
+        rtc.set_minutes(34).unwrap();
+        rtc.set_seconds(56).unwrap();
+        rtc.set_hours(Hours::Hour12pm(1)).unwrap();
+        assert_eq!(rtc.mode(), Mode::Hour12);
+        block_on(rtc_async.set_minutes(34)).unwrap();
+        block_on(rtc_async.set_seconds(56)).unwrap();
+        block_on(rtc_async.set_hours(Hours::Hour12pm(1))).unwrap();
+        assert_eq!(rtc_async.mode(), Mode::Hour12);
+        spi.done();
+        spi_async.done();
+    }
+
+    #[test]
+    fn both_drivers_decode_the_same_registers() {
+        let regs = [0x56, 0x34, 0x12, 0x19, 0x05, 0x03, 0x21];
+        let (mut rtc, mut spi, mut rtc_async, mut spi_async) = both(&[
+            Op::Burst(&regs),
+            Op::Burst(&regs),
+            Op::Read(0x84, 0x12),
+            Op::Read(0x8C, 0xFA),
+        ]);
+        assert_eq!(
+            rtc.get_datetime().unwrap(),
+            block_on(rtc_async.get_datetime()).unwrap()
+        );
+        assert_eq!(
+            rtc.get_datetime_checked().unwrap(),
+            block_on(rtc_async.get_datetime_checked()).unwrap()
+        );
+        assert_eq!(
+            rtc.get_hours().unwrap(),
+            block_on(rtc_async.get_hours()).unwrap()
+        );
+        assert!(rtc.get_year().is_err());
+        assert!(block_on(rtc_async.get_year()).is_err());
+        spi.done();
+        spi_async.done();
+    }
+
+    #[test]
+    fn both_drivers_write_the_calendar_registers_only() {
+        let ops = [
+            Op::Read(0x8E, 0x80),
+            Op::Write(0x8E, 0x00),
+            Op::Write(0x8C, 0x21),
+            Op::Write(0x88, 0x11),
+            Op::Write(0x86, 0x19),
+            Op::Write(0x8A, 0x05),
+        ];
+        let (mut rtc, mut spi, mut rtc_async, mut spi_async) = both(&ops);
+        let calendar = Calendar::new(5, 19, 11, 2021).unwrap();
+        rtc.set_calendar(calendar).unwrap();
+        block_on(rtc_async.set_calendar(calendar)).unwrap();
+        spi.done();
+        spi_async.done();
+    }
+
+    #[test]
+    fn both_drivers_reject_years_the_chip_cannot_store() {
+        let (mut rtc, mut spi, mut rtc_async, mut spi_async) = both(&[
+            Op::Read(0x8E, 0x00),
+            Op::Write(0x8C, 0x00),
+            Op::Write(0x8C, 0x99),
+        ]);
+        rtc.set_year(2000).unwrap();
+        rtc.set_year(2099).unwrap();
+        block_on(rtc_async.set_year(2000)).unwrap();
+        block_on(rtc_async.set_year(2099)).unwrap();
+        let clock = Clock::new(Hours::Hour24(12), 0, 0).unwrap();
+        for year in [1999, 2100, 2155] {
+            let calendar = Calendar {
+                day: 1,
+                date: 1,
+                month: 1,
+                year,
+            };
+            let datetime = DateTime { clock, calendar };
+            assert!(rejected(rtc.set_year(year)));
+            assert!(rejected(rtc.set_calendar(calendar)));
+            assert!(rejected(rtc.set_calendar_autoday(calendar)));
+            assert!(rejected(rtc.set_calendar_burst(calendar)));
+            assert!(rejected(rtc.set_clock_calendar(clock, calendar)));
+            assert!(rejected(rtc.set_datetime(datetime)));
+            assert!(rejected(block_on(rtc_async.set_year(year))));
+            assert!(rejected(block_on(rtc_async.set_calendar(calendar))));
+            assert!(rejected(block_on(rtc_async.set_calendar_autoday(calendar))));
+            assert!(rejected(block_on(rtc_async.set_calendar_burst(calendar))));
+            assert!(rejected(block_on(
+                rtc_async.set_clock_calendar(clock, calendar)
+            )));
+            assert!(rejected(block_on(rtc_async.set_datetime(datetime))));
+        }
+        spi.done();
+        spi_async.done();
+    }
+
+    #[test]
+    fn both_drivers_keep_the_clock_bytes_of_a_calendar_burst() {
+        let (mut rtc, mut spi, mut rtc_async, mut spi_async) = both(&[
+            // Halted at 7:24:56 PM in 12-hour format
+            Op::Burst(&[0xD6, 0x24, 0xA7]),
+            Op::Read(0x8E, 0x00),
+            Op::WriteBytes(&[0xBE, 0xD6, 0x24, 0xA7, 0x29, 0x02, 0x04, 0x24, 0x00]),
+        ]);
+        let calendar = Calendar::new(4, 29, 2, 2024).unwrap();
+        rtc.set_calendar_burst(calendar).unwrap();
+        block_on(rtc_async.set_calendar_burst(calendar)).unwrap();
+        assert_eq!(rtc.mode(), Mode::Hour12);
+        assert_eq!(rtc_async.mode(), Mode::Hour12);
+        spi.done();
+        spi_async.done();
+    }
+
+    #[test]
+    fn both_drivers_restore_the_write_protection_around_a_closure() {
+        let (mut rtc, mut spi, mut rtc_async, mut spi_async) = both(&[
+            Op::Read(0x8E, 0x80),
+            Op::Write(0x8E, 0x00),
+            Op::Write(0x82, 0x34),
+            Op::Read(0x80, 0x12),
+            Op::Write(0x80, 0x56),
+            Op::Write(0x8E, 0x80),
+            // The closure fails, the protection is restored anyway
+            Op::Write(0x8E, 0x00),
+            Op::Write(0x8E, 0x80),
+        ]);
+        rtc.with_writes_enabled(|rtc| {
+            rtc.set_minutes(34)?;
+            rtc.set_seconds(56)
+        })
+        .unwrap();
+        assert!(rejected(rtc.with_writes_enabled(|rtc| rtc.set_minutes(60))));
+        block_on(rtc_async.with_writes_enabled(async |rtc| {
+            rtc.set_minutes(34).await?;
+            rtc.set_seconds(56).await
+        }))
+        .unwrap();
+        assert!(rejected(block_on(
+            rtc_async.with_writes_enabled(async |rtc| rtc.set_minutes(60).await)
+        )));
+        spi.done();
+        spi_async.done();
+    }
+
+    #[test]
+    fn both_drivers_leave_an_unprotected_chip_unprotected() {
+        let (mut rtc, mut spi, mut rtc_async, mut spi_async) =
+            both(&[Op::Read(0x8E, 0x00), Op::Write(0x82, 0x34)]);
+        rtc.with_writes_enabled(|rtc| rtc.set_minutes(34)).unwrap();
+        block_on(rtc_async.with_writes_enabled(async |rtc| rtc.set_minutes(34).await)).unwrap();
+        spi.done();
+        spi_async.done();
+    }
+}
@@ -0,0 +1,413 @@
+//! SPI interfaces used to talk to the DS1302
+
+use crate::Ds1302Error;
+use core::cell::RefCell;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+/// Bus access of the driver. One call is one complete transaction:
+/// CE is asserted before the first byte and deasserted after the last one.
+pub trait Interface {
+    /// Error of the SPI bus
+    type SpiError;
+    /// Error of the chip select pin
+    type PinError;
+
+    /// Send `bytes[0]` as the command byte and read the answer into the rest of `bytes`
+    fn transfer(
+        &mut self,
+        bytes: &mut [u8],
+    ) -> Result<(), Ds1302Error<Self::SpiError, Self::PinError>>;
+
+    /// Send `bytes`, starting with the command byte
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Ds1302Error<Self::SpiError, Self::PinError>>;
+}
+
+/// Level of the chip select pin that selects the DS1302
+///
+/// The CE input of the chip is active high. A pin driving CE through an inverting transistor
+/// selects the chip when it is low.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CsPolarity {
+    #[default]
+    ActiveHigh,
+    ActiveLow,
+}
+
+impl CsPolarity {
+    // Whether the pin is high when the chip is selected (`active`) or deselected
+    fn is_high(self, active: bool) -> bool {
+        (self == CsPolarity::ActiveHigh) == active
+    }
+}
+
+fn set_cs<CS: OutputPin>(cs: &mut CS, polarity: CsPolarity, active: bool) -> Result<(), CS::Error> {
+    if polarity.is_high(active) {
+        cs.set_high()
+    } else {
+        cs.set_low()
+    }
+}
+
+/// `embedded-hal` 0.2 SPI bus with a separate chip select (CE) pin
+pub struct SpiInterface<SPI, CS> {
+    pub(crate) spi: SPI,
+    pub(crate) cs: CS,
+    pub(crate) polarity: CsPolarity,
+}
+
+impl<SPI, CS> SpiInterface<SPI, CS> {
+    /// Create the interface from the SPI bus and the active high chip select pin
+    pub fn new(spi: SPI, cs: CS) -> Self {
+        SpiInterface::with_cs_polarity(spi, cs, CsPolarity::ActiveHigh)
+    }
+
+    /// Create the interface from the SPI bus and a chip select pin with the given polarity
+    pub fn with_cs_polarity(spi: SPI, cs: CS, polarity: CsPolarity) -> Self {
+        SpiInterface { spi, cs, polarity }
+    }
+
+    /// Release the SPI bus and the chip select pin
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+}
+
+impl<SPI, CS, PinError> SpiInterface<SPI, CS>
+where
+    CS: OutputPin<Error = PinError>,
+{
+    // Run `f` with CE asserted. CE is deasserted even if `f` fails, whose error is returned first.
+    fn transaction<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut SPI) -> Result<T, E>,
+    ) -> Result<T, Ds1302Error<E, PinError>> {
+        set_cs(&mut self.cs, self.polarity, true).map_err(Ds1302Error::Pin)?;
+        let result = f(&mut self.spi);
+        let deasserted = set_cs(&mut self.cs, self.polarity, false);
+        let value = result.map_err(Ds1302Error::Spi)?;
+        deasserted.map_err(Ds1302Error::Pin)?;
+        Ok(value)
+    }
+}
+
+impl<SPI, CS, E, PinError> Interface for SpiInterface<SPI, CS>
+where
+    SPI: spi::Transfer<u8, Error = E> + spi::Write<u8, Error = E>,
+    CS: OutputPin<Error = PinError>,
+{
+    type SpiError = E;
+    type PinError = PinError;
+
+    fn transfer(&mut self, bytes: &mut [u8]) -> Result<(), Ds1302Error<E, PinError>> {
+        self.transaction(|spi| spi.transfer(bytes).map(|_| ()))
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Ds1302Error<E, PinError>> {
+        self.transaction(|spi| spi.write(bytes))
+    }
+}
+
+/// `embedded-hal` 0.2 SPI bus shared with other devices through a [`RefCell`], with the CE pin
+/// of the DS1302
+///
+/// The bus is only borrowed for the duration of each transaction, so the other drivers can
+/// borrow it between two calls of this driver. A borrow held by another driver while this
+/// driver is called panics, like any [`RefCell::borrow_mut`].
+pub struct SharedSpiInterface<'a, SPI, CS> {
+    pub(crate) spi: &'a RefCell<SPI>,
+    pub(crate) cs: CS,
+    pub(crate) polarity: CsPolarity,
+}
+
+impl<'a, SPI, CS> SharedSpiInterface<'a, SPI, CS> {
+    /// Create the interface from the shared SPI bus and the active high chip select pin
+    pub fn new(spi: &'a RefCell<SPI>, cs: CS) -> Self {
+        SharedSpiInterface::with_cs_polarity(spi, cs, CsPolarity::ActiveHigh)
+    }
+
+    /// Create the interface from the shared SPI bus and a chip select pin with the given polarity
+    pub fn with_cs_polarity(spi: &'a RefCell<SPI>, cs: CS, polarity: CsPolarity) -> Self {
+        SharedSpiInterface { spi, cs, polarity }
+    }
+
+    /// Release the chip select pin, the bus stays with its owner
+    pub fn release(self) -> CS {
+        self.cs
+    }
+}
+
+impl<SPI, CS, PinError> SharedSpiInterface<'_, SPI, CS>
+where
+    CS: OutputPin<Error = PinError>,
+{
+    // Run `f` on the borrowed bus with CE asserted, CE is deasserted even if `f` fails
+    fn transaction<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut SPI) -> Result<T, E>,
+    ) -> Result<T, Ds1302Error<E, PinError>> {
+        let mut spi = self.spi.borrow_mut();
+        set_cs(&mut self.cs, self.polarity, true).map_err(Ds1302Error::Pin)?;
+        let result = f(&mut spi);
+        let deasserted = set_cs(&mut self.cs, self.polarity, false);
+        let value = result.map_err(Ds1302Error::Spi)?;
+        deasserted.map_err(Ds1302Error::Pin)?;
+        Ok(value)
+    }
+}
+
+impl<SPI, CS, E, PinError> Interface for SharedSpiInterface<'_, SPI, CS>
+where
+    SPI: spi::Transfer<u8, Error = E> + spi::Write<u8, Error = E>,
+    CS: OutputPin<Error = PinError>,
+{
+    type SpiError = E;
+    type PinError = PinError;
+
+    fn transfer(&mut self, bytes: &mut [u8]) -> Result<(), Ds1302Error<E, PinError>> {
+        self.transaction(|spi| spi.transfer(bytes).map(|_| ()))
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Ds1302Error<E, PinError>> {
+        self.transaction(|spi| spi.write(bytes))
+    }
+}
+
+/// `embedded-hal` 0.2 SPI bus that drives the chip select (CE) itself, e.g. with the hardware
+/// NSS output of the SPI peripheral
+///
+/// Every call to the bus must be one complete transaction with CE asserted throughout. The CE
+/// inactive time between two transactions is still waited for by the [`Delay`](crate::Delay) of
+/// the driver, the bus must not reassert CE sooner by itself.
+pub struct SpiNoCsInterface<SPI> {
+    pub(crate) spi: SPI,
+}
+
+impl<SPI> SpiNoCsInterface<SPI> {
+    /// Create the interface from the SPI bus
+    pub fn new(spi: SPI) -> Self {
+        SpiNoCsInterface { spi }
+    }
+
+    /// Release the SPI bus
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+impl<SPI, E> Interface for SpiNoCsInterface<SPI>
+where
+    SPI: spi::Transfer<u8, Error = E> + spi::Write<u8, Error = E>,
+{
+    type SpiError = E;
+    type PinError = core::convert::Infallible;
+
+    fn transfer(&mut self, bytes: &mut [u8]) -> Result<(), Ds1302Error<E, Self::PinError>> {
+        self.spi
+            .transfer(bytes)
+            .map(|_| ())
+            .map_err(Ds1302Error::Spi)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Ds1302Error<E, Self::PinError>> {
+        self.spi.write(bytes).map_err(Ds1302Error::Spi)
+    }
+}
+
+/// `embedded-hal` 1.0 SPI device, the chip select (CE) is handled by the device
+#[cfg(feature = "eh1")]
+pub struct SpiDeviceInterface<SPI> {
+    pub(crate) spi: SPI,
+}
+
+#[cfg(feature = "eh1")]
+impl<SPI> SpiDeviceInterface<SPI> {
+    /// Create the interface from the SPI device
+    pub fn new(spi: SPI) -> Self {
+        SpiDeviceInterface { spi }
+    }
+
+    /// Release the SPI device
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<SPI> Interface for SpiDeviceInterface<SPI>
+where
+    SPI: eh1::spi::SpiDevice<u8>,
+{
+    type SpiError = SPI::Error;
+    type PinError = core::convert::Infallible;
+
+    fn transfer(
+        &mut self,
+        bytes: &mut [u8],
+    ) -> Result<(), Ds1302Error<SPI::Error, Self::PinError>> {
+        self.spi.transfer_in_place(bytes).map_err(Ds1302Error::Spi)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Ds1302Error<SPI::Error, Self::PinError>> {
+        self.spi.write(bytes).map_err(Ds1302Error::Spi)
+    }
+}
+
+/// `embedded-hal` 1.0 SPI bus with a separate chip select (CE) pin
+#[cfg(feature = "eh1")]
+pub struct SpiBusInterface<SPI, CS> {
+    pub(crate) spi: SPI,
+    pub(crate) cs: CS,
+    pub(crate) polarity: CsPolarity,
+}
+
+#[cfg(feature = "eh1")]
+impl<SPI, CS> SpiBusInterface<SPI, CS> {
+    /// Create the interface from the SPI bus and the active high chip select pin
+    pub fn new(spi: SPI, cs: CS) -> Self {
+        SpiBusInterface::with_cs_polarity(spi, cs, CsPolarity::ActiveHigh)
+    }
+
+    /// Create the interface from the SPI bus and a chip select pin with the given polarity
+    pub fn with_cs_polarity(spi: SPI, cs: CS, polarity: CsPolarity) -> Self {
+        SpiBusInterface { spi, cs, polarity }
+    }
+
+    /// Release the SPI bus and the chip select pin
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+}
+
+#[cfg(feature = "eh1")]
+fn set_cs_eh1<CS: eh1::digital::OutputPin>(
+    cs: &mut CS,
+    polarity: CsPolarity,
+    active: bool,
+) -> Result<(), CS::Error> {
+    if polarity.is_high(active) {
+        cs.set_high()
+    } else {
+        cs.set_low()
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<SPI, CS> SpiBusInterface<SPI, CS>
+where
+    SPI: eh1::spi::SpiBus<u8>,
+    CS: eh1::digital::OutputPin,
+{
+    // Run `f` and flush the bus with CE asserted. CE is deasserted even if the bus fails,
+    // whose error is returned first.
+    fn transaction(
+        &mut self,
+        f: impl FnOnce(&mut SPI) -> Result<(), SPI::Error>,
+    ) -> Result<(), Ds1302Error<SPI::Error, CS::Error>> {
+        set_cs_eh1(&mut self.cs, self.polarity, true).map_err(Ds1302Error::Pin)?;
+        let result = f(&mut self.spi).and_then(|_| self.spi.flush());
+        let deasserted = set_cs_eh1(&mut self.cs, self.polarity, false);
+        result.map_err(Ds1302Error::Spi)?;
+        deasserted.map_err(Ds1302Error::Pin)
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<SPI, CS> Interface for SpiBusInterface<SPI, CS>
+where
+    SPI: eh1::spi::SpiBus<u8>,
+    CS: eh1::digital::OutputPin,
+{
+    type SpiError = SPI::Error;
+    type PinError = CS::Error;
+
+    fn transfer(&mut self, bytes: &mut [u8]) -> Result<(), Ds1302Error<SPI::Error, CS::Error>> {
+        self.transaction(|spi| spi.transfer_in_place(bytes))
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Ds1302Error<SPI::Error, CS::Error>> {
+        self.transaction(|spi| spi.write(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{BusFault, FailingSpi, NoWait, Pin, PinFault};
+    use crate::{Mode, DS1302};
+
+    #[test]
+    fn spi_error_is_returned_by_the_driver() {
+        let result = DS1302::new(FailingSpi, Pin::default(), Mode::Hour24, NoWait);
+        assert!(matches!(result, Err(Ds1302Error::Spi(BusFault))));
+    }
+
+    #[test]
+    fn pin_error_is_returned() {
+        let pin = Pin {
+            fails: true,
+            high: false,
+        };
+        let mut interface = SpiInterface::new(FailingSpi, pin);
+        let result = interface.write(&[0x80, 0x00]);
+        assert!(matches!(result, Err(Ds1302Error::Pin(PinFault))));
+    }
+
+    #[test]
+    fn cs_is_deasserted_when_the_spi_fails() {
+        use embedded_hal_mock::eh0::digital::{Mock, State, Transaction};
+
+        let mut cs = Mock::new(&[
+            Transaction::set(State::High),
+            Transaction::set(State::Low),
+            Transaction::set(State::High),
+            Transaction::set(State::Low),
+        ]);
+        let mut interface = SpiInterface::new(FailingSpi, cs.clone());
+        let result = interface.transfer(&mut [0x81, 0x00]);
+        assert!(matches!(result, Err(Ds1302Error::Spi(BusFault))));
+        let result = interface.write(&[0x80, 0x00]);
+        assert!(matches!(result, Err(Ds1302Error::Spi(BusFault))));
+        cs.done();
+    }
+
+    #[test]
+    fn active_low_cs_is_released_high_when_the_spi_fails() {
+        let mut interface =
+            SpiInterface::with_cs_polarity(FailingSpi, Pin::default(), CsPolarity::ActiveLow);
+        let result = interface.write(&[0x80, 0x00]);
+        assert!(matches!(result, Err(Ds1302Error::Spi(BusFault))));
+        let (_, cs) = interface.release();
+        assert!(cs.high);
+    }
+
+    #[test]
+    fn cs_pin_sequence_of_both_polarities() {
+        use crate::mock::{read, write};
+        use embedded_hal_mock::eh0::digital::{
+            Mock as PinMock, State, Transaction as PinTransaction,
+        };
+        use embedded_hal_mock::eh0::spi::Mock as SpiMock;
+
+        for (polarity, active, inactive) in [
+            (CsPolarity::ActiveHigh, State::High, State::Low),
+            (CsPolarity::ActiveLow, State::Low, State::High),
+        ] {
+            let mut spi = SpiMock::new(&[write(0x80, 0x00), read(0x80, 0x12)]);
+            let mut cs = PinMock::new(&[
+                PinTransaction::set(active),
+                PinTransaction::set(inactive),
+                PinTransaction::set(active),
+                PinTransaction::set(inactive),
+            ]);
+            let mut interface = SpiInterface::with_cs_polarity(spi.clone(), cs.clone(), polarity);
+            interface.write(&[0x80, 0x00]).unwrap();
+            let mut bytes = [0x81, 0x00];
+            interface.transfer(&mut bytes).unwrap();
+            assert_eq!(bytes, [0x00, 0x12]);
+            spi.done();
+            cs.done();
+        }
+    }
+}
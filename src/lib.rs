@@ -22,31 +22,174 @@
 //! - Programmable Trickle Charger configuration
 //! - 31 x 8 Battery-Backed General-Purpose RAM operations
 //!
+//! ## Burst access
+//!
+//! A clock burst always starts at the seconds register and runs through minutes, hours, date,
+//! month, day, year and write protect. Reads may stop early, so [`DS1302::get_clock`] reads only
+//! the first three bytes and [`DS1302::get_clock_calendar`] all seven in one transaction.
+//! Writes must send all eight bytes, so only [`DS1302::set_clock_calendar`] and
+//! [`DS1302::set_datetime`] are burst writes. A clock-only or calendar-only burst write isn't
+//! possible: [`DS1302::set_clock`] and [`DS1302::set_calendar`] write their registers one by one,
+//! clearing the write protection once. The RAM burst always starts at index 0, a range of the RAM
+//! at another index is accessed byte by byte.
+//!
 
 #![no_std]
 
-use core::convert::From;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+pub use builder::DS1302Builder;
+use core::cell::RefCell;
+use core::cmp::Ordering;
+use core::convert::{From, TryFrom};
+use core::ops::Range;
+use date::{datetime_to_unix, day_of_year, iso_week, unix_to_datetime, LAST_UNIX_SECOND};
+pub use date::{days_in_month, is_leap_year, weekday, weekday_short_name, Month, Weekday};
+use driver::driver_methods;
 use embedded_hal as hal;
 use fugit::ExtU32;
 use hal::blocking::spi;
 use hal::digital::v2::OutputPin;
-pub use registers::{Ds, Rs};
-use registers::{Register, TrickleCharger};
+pub use interface::{CsPolarity, Interface, SharedSpiInterface, SpiInterface, SpiNoCsInterface};
+#[cfg(feature = "eh1")]
+pub use interface::{SpiBusInterface, SpiDeviceInterface};
+#[cfg(feature = "no-delay")]
+pub use no_delay::NoDelay;
+use ram::crc_region_is_valid;
+pub use ram::{crc8, RamBuffer, RamCounter};
+pub use registers::{tc_describe, tc_max_current_ua, Command, Ds, Rs, TC_MAX_SAFE_CURRENT_UA};
+use registers::{tc_is_safe, Register, TrickleCharger};
+#[cfg(feature = "std")]
+pub use std_delay::StdDelay;
+#[cfg(feature = "embedded-storage")]
+pub use storage::RamStorage;
+
+/// Minimum CE inactive time between two transactions (tCWH) in ns, the default wait of the driver
+///
+/// The timing constants are the datasheet minimums at VCC = 2.0 V, which also hold at higher
+/// supply voltages.
+pub const T_CE_INACTIVE_NS: u32 = 4_000;
+/// Minimum time from CE high to the first rising clock edge (tCC) in ns
+pub const T_CE_TO_CLK_NS: u32 = 4_000;
+/// Minimum time from the last clock edge to CE low (tCCH) in ns
+pub const T_CLK_TO_CE_NS: u32 = 240;
+/// Minimum clock high time (tCH) in ns
+pub const T_CLK_HIGH_NS: u32 = 1_000;
+/// Minimum clock low time (tCL) in ns
+pub const T_CLK_LOW_NS: u32 = 1_000;
+/// Maximum SPI clock frequency (fCLK) in Hz
+pub const F_CLK_MAX_HZ: u32 = 500_000;
+
+/// Size of the battery-backed RAM in bytes, the indexes are `0..RAM_SIZE`
+pub const RAM_SIZE: usize = 31;
 
 const CLOCK_HALT_FLAG: u8 = 0x80;
 const WRITE_PROTECT_BIT: u8 = 0x80;
-const READ_BIT: u8 = 0x1;
-const HOUR_12_BIT: u8 = 0x80;
-const HOUR_PM_BIT: u8 = 0x20;
+/// Bit 7 of the hours register, set in the 12-hour format
+pub const HOUR_12_BIT: u8 = 0x80;
+/// Bit 5 of the hours register, set for PM in the 12-hour format.
+/// In the 24-hour format it is the second bit of the tens of hours.
+pub const HOUR_PM_BIT: u8 = 0x20;
+// Reads of the seconds register before `wait_for_second_change` gives up, more than one second
+// even at the fastest bus
+const SECOND_CHANGE_POLLS: u32 = 500_000;
 
 /// DS1302 error
+///
+/// The bus error types default to [`Infallible`](core::convert::Infallible) for the conversions that
+/// don't touch the bus, so their error is simply `Ds1302Error`.
 #[derive(Debug)]
-pub enum Ds1302Error {
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Ds1302Error<
+    SpiError = core::convert::Infallible,
+    PinError = core::convert::Infallible,
+    TimerError = core::convert::Infallible,
+> {
     Parameter,
-    Spi,
+    /// Error of the underlying SPI bus
+    Spi(SpiError),
+    /// Error of the chip select pin
+    Pin(PinError),
+    /// Error of the [`Delay`] timer
+    Timer(TimerError),
+    /// The chip returned values out of range, e.g. after losing its backup supply
+    InvalidData,
+    /// A value read back after a write differs from the written one
+    Verify,
     Unknown,
 }
 
+impl<SpiError, PinError, TimerError> core::fmt::Display
+    for Ds1302Error<SpiError, PinError, TimerError>
+where
+    SpiError: core::fmt::Debug,
+    PinError: core::fmt::Debug,
+    TimerError: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Ds1302Error::Parameter => f.write_str("invalid parameter"),
+            Ds1302Error::Spi(e) => write!(f, "SPI bus error: {:?}", e),
+            Ds1302Error::Pin(e) => write!(f, "chip select pin error: {:?}", e),
+            Ds1302Error::Timer(e) => write!(f, "timer error: {:?}", e),
+            Ds1302Error::InvalidData => f.write_str("invalid data read from the chip"),
+            Ds1302Error::Verify => f.write_str("read back value differs from the written one"),
+            Ds1302Error::Unknown => f.write_str("unknown error"),
+        }
+    }
+}
+
+/// The bus and timer errors of `embedded-hal` drivers usually don't implement `Error`,
+/// so they are only part of the message and not returned by `source`.
+impl<SpiError, PinError, TimerError> core::error::Error
+    for Ds1302Error<SpiError, PinError, TimerError>
+where
+    SpiError: core::fmt::Debug,
+    PinError: core::fmt::Debug,
+    TimerError: core::fmt::Debug,
+{
+}
+
+impl<SpiError, PinError> Ds1302Error<SpiError, PinError> {
+    // Bus errors of an `Interface` don't involve the timer
+    fn with_timer<TimerError>(self) -> Ds1302Error<SpiError, PinError, TimerError> {
+        match self {
+            Ds1302Error::Parameter => Ds1302Error::Parameter,
+            Ds1302Error::Spi(e) => Ds1302Error::Spi(e),
+            Ds1302Error::Pin(e) => Ds1302Error::Pin(e),
+            Ds1302Error::Timer(e) => match e {},
+            Ds1302Error::InvalidData => Ds1302Error::InvalidData,
+            Ds1302Error::Verify => Ds1302Error::Verify,
+            Ds1302Error::Unknown => Ds1302Error::Unknown,
+        }
+    }
+}
+
+impl Ds1302Error {
+    // Value errors don't involve the bus or the timer
+    fn into_driver<SpiError, PinError, TimerError>(
+        self,
+    ) -> Ds1302Error<SpiError, PinError, TimerError> {
+        match self {
+            Ds1302Error::Parameter => Ds1302Error::Parameter,
+            Ds1302Error::Spi(e) => match e {},
+            Ds1302Error::Pin(e) => match e {},
+            Ds1302Error::Timer(e) => match e {},
+            Ds1302Error::InvalidData => Ds1302Error::InvalidData,
+            Ds1302Error::Verify => Ds1302Error::Verify,
+            Ds1302Error::Unknown => Ds1302Error::Unknown,
+        }
+    }
+}
+
+/// Error of the [`DS1302`] driver on the SPI [`Interface`] `IF` with the [`Delay`] timer `CLK`
+pub type DriverError<IF, CLK, const TIMER_HZ: u32> = Ds1302Error<
+    <IF as Interface>::SpiError,
+    <IF as Interface>::PinError,
+    <CLK as Delay<TIMER_HZ>>::Error,
+>;
+
 /// For timing `ds1302` uses [fugit](https://lib.rs/crates/fugit) crate which only provides `Duration` and `Instant` types.
 /// It does not provide any clock or timer traits.
 /// Therefore `ds1302` has its own `Delay` trait that provides all timing capabilities that are needed for the library.
@@ -65,24 +208,93 @@ pub trait Delay<const TIMER_HZ: u32> {
     /// Must return `nb::Error::WouldBlock` if countdown `duration` is not yet over.
     /// Must return `OK(())` as soon as countdown `duration` has expired.
     fn wait(&mut self) -> nb::Result<(), Self::Error>;
+
+    /// Abandon a running countdown, e.g. to stop a hardware timer.
+    /// After `cancel` the timer may be started again with `start`.
+    /// Calling it without a running countdown must not fail.
+    ///
+    /// The driver cancels the countdown of the CE inactive time when it is destroyed.
+    /// The default implementation does nothing.
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
 }
 
 ///DS1302 RTCC driver
-pub struct DS1302<SPI, CS, CLK, const TIMER_HZ: u32>
+///
+///`IF` is the SPI [`Interface`] of the chip, see [`DS1302::new`] for the `embedded-hal` 0.2 SPI bus
+///
+///The getters of a single field like [`DS1302::get_seconds`] read one register each, so combining
+///them can straddle a rollover: 59 seconds read just before the minute changes and the new
+///minute read after it are a minute off. The chip copies all time registers to a buffer when CE
+///is asserted, so [`DS1302::get_datetime`] and the other burst reads always return fields that
+///belong together.
+pub struct DS1302<IF, CLK, const TIMER_HZ: u32>
 where
     CLK: Delay<TIMER_HZ>,
 {
-    spi: SPI,
-    cs: CS,
+    iface: IF,
     timer: CLK,
+    bit_order: BitOrder,
+    // CE inactive time between two transactions
+    ce_delay: fugit::TimerDurationU32<TIMER_HZ>,
+    // Last known state of the write protect bit, `None` until it has been read
+    write_protect: Option<bool>,
+    // CH flag found set by the constructor
+    clock_was_halted: bool,
+    // Hour format of the last write to the hours register
+    mode: Mode,
+}
+///Bit order of the SPI bus
+///
+///The DS1302 shifts data LSB first. On a bus that can only send MSB first the driver reverses
+///the bits of every byte in software.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BitOrder {
+    LsbFirst,
+    MsbFirst,
+}
+
+impl BitOrder {
+    // Convert between the bus and the chip bit order
+    fn apply(self, bytes: &mut [u8]) {
+        if self == BitOrder::MsbFirst {
+            for b in bytes.iter_mut() {
+                *b = b.reverse_bits();
+            }
+        }
+    }
 }
+
 ///Hour format: 12-hour (AM/PM) or 24-hour
-#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Mode {
     Hour24,
     Hour12,
 }
+
+impl Mode {
+    // Hour format of the hours register
+    fn of_hours_register(byte: u8) -> Self {
+        if byte & HOUR_12_BIT != 0 {
+            Mode::Hour12
+        } else {
+            Mode::Hour24
+        }
+    }
+}
+
 ///Hour information: 12-hour (AM/PM) or 24-hour
+///
+///24-hour values are 0..=23, 12-hour values are 1..=12 (midnight is `Hour12am(12)`, noon is `Hour12pm(12)`)
+///
+///Equality and order compare the hour of the day, not the format: `Hour24(13) == Hour12pm(1)`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Hours {
     Hour24(u8),
     Hour12am(u8),
@@ -90,22 +302,70 @@ pub enum Hours {
 }
 
 impl Hours {
+    ///24-hour value, fails with `Ds1302Error::Parameter` outside of 0..=23
+    pub fn from_24(hour: u8) -> Result<Self, Ds1302Error> {
+        if hour > 23 {
+            return Err(Ds1302Error::Parameter);
+        }
+        Ok(Hours::Hour24(hour))
+    }
+
+    ///12-hour value, fails with `Ds1302Error::Parameter` outside of 1..=12
+    ///
+    ///Midnight is `from_12(12, false)`, noon is `from_12(12, true)`.
+    pub fn from_12(hour: u8, pm: bool) -> Result<Self, Ds1302Error> {
+        if !(1..=12).contains(&hour) {
+            return Err(Ds1302Error::Parameter);
+        }
+        Ok(if pm {
+            Hours::Hour12pm(hour)
+        } else {
+            Hours::Hour12am(hour)
+        })
+    }
+
+    // 12-hour values are 1..=12 like on the chip: midnight is 12 AM, noon is 12 PM
     fn convert(&self) -> Self {
         match *self {
-            Hours::Hour24(h) => {
-                if h >= 12 {
-                    Hours::Hour12pm(h - 12)
-                } else {
-                    Hours::Hour12am(h)
-                }
-            }
-            Hours::Hour12pm(h) => Hours::Hour24(h + 12),
-            Hours::Hour12am(h) => Hours::Hour24(h),
+            Hours::Hour24(h) => match h {
+                0 => Hours::Hour12am(12),
+                1..=11 => Hours::Hour12am(h),
+                12 => Hours::Hour12pm(12),
+                _ => Hours::Hour12pm(h - 12),
+            },
+            // Out of range 12-hour values must not overflow, they can come from deserialization
+            Hours::Hour12pm(h) => Hours::Hour24(h % 12 + 12),
+            Hours::Hour12am(h) => Hours::Hour24(h % 12),
+        }
+    }
+
+    // 24-hour values must be 0..=23, 12-hour values 1..=12
+    fn is_valid(&self) -> bool {
+        match *self {
+            Hours::Hour24(h) => h <= 23,
+            Hours::Hour12am(h) | Hours::Hour12pm(h) => (1..=12).contains(&h),
+        }
+    }
+
+    // The hour as 0..=23
+    fn hour24(&self) -> u8 {
+        match *self {
+            Hours::Hour24(h) => h,
+            _ => self.convert().hour().0,
+        }
+    }
+
+    // The hour as shown on a display and the AM/PM suffix of 12-hour values
+    fn display_parts(&self) -> (u8, &'static str) {
+        match *self {
+            Hours::Hour24(h) => (h, ""),
+            Hours::Hour12am(h) => (h, " AM"),
+            Hours::Hour12pm(h) => (h, " PM"),
         }
     }
 
     /// Get the hour.
-    /// return.1: None => Hour24 mode; Some(false) => pm; Some(true) => am;
+    /// return.1: None => Hour24 mode; Some(false) => am; Some(true) => pm;
     pub fn hour(&self) -> (u8, Option<bool>) {
         match *self {
             Hours::Hour24(h) => (h, None),
@@ -113,6 +373,31 @@ impl Hours {
             Hours::Hour12pm(h) => (h, Some(true)),
         }
     }
+
+    /// Whether a 12-hour value is PM, `None` for a 24-hour value
+    pub fn is_pm(&self) -> Option<bool> {
+        self.hour().1
+    }
+}
+
+impl PartialEq for Hours {
+    fn eq(&self, other: &Self) -> bool {
+        self.hour24() == other.hour24()
+    }
+}
+
+impl Eq for Hours {}
+
+impl PartialOrd for Hours {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Hours {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.hour24().cmp(&other.hour24())
+    }
 }
 
 impl From<u8> for Hours {
@@ -134,6 +419,16 @@ impl From<u8> for Hours {
     }
 }
 
+/// The hour of the day as 0..=23, e.g. 13 for `Hour12pm(1)`
+///
+/// Unlike `From<Hours>`, which gives the BCD register value, this converts a reference.
+impl From<&Hours> for u8 {
+    fn from(h: &Hours) -> Self {
+        h.hour24()
+    }
+}
+
+/// BCD value of the hours register, including the 12-hour and PM flags
 impl From<Hours> for u8 {
     fn from(h: Hours) -> Self {
         match h {
@@ -145,12 +440,131 @@ impl From<Hours> for u8 {
 }
 
 ///Clock information
+///
+///The fields are public, [`Clock::new`] checks the values before they reach the chip.
+///Clocks are compared by [`Clock::seconds_since_midnight`], so the same time of the day in
+///12-hour and 24-hour format is equal even though the chip stores different bytes for it.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Clock {
     pub hours: Hours,
     pub minutes: u8,
     pub seconds: u8,
 }
+
+impl Clock {
+    ///Create a clock, fails with `Ds1302Error::Parameter` if a value is out of range
+    pub fn new(hours: Hours, minutes: u8, seconds: u8) -> Result<Self, Ds1302Error> {
+        let clock = Clock {
+            hours,
+            minutes,
+            seconds,
+        };
+        if !clock.is_valid() {
+            return Err(Ds1302Error::Parameter);
+        }
+        Ok(clock)
+    }
+
+    ///Create a 24-hour clock from the seconds since midnight,
+    ///fails with `Ds1302Error::Parameter` for 86400 and more
+    pub fn from_seconds_since_midnight(seconds: u32) -> Result<Self, Ds1302Error> {
+        if seconds >= 86_400 {
+            return Err(Ds1302Error::Parameter);
+        }
+        Ok(Clock {
+            hours: Hours::Hour24((seconds / 3600) as u8),
+            minutes: (seconds % 3600 / 60) as u8,
+            seconds: (seconds % 60) as u8,
+        })
+    }
+
+    ///Hours, minutes and seconds packed in BCD as `0x00_HH_MM_SS`
+    ///
+    ///- bits 31..=24: 0
+    ///- bits 23..=16: the hours register, in BCD with the 12-hour flag in bit 23 and the PM flag
+    ///  in bit 21
+    ///- bits 15..=8: the minutes in BCD
+    ///- bits 7..=0: the seconds in BCD, without the clock halt flag
+    ///
+    ///The bytes are the ones written to the chip, e.g. `0x00_12_34_56` for 12:34:56 in 24-hour
+    ///format and `0x00_A1_00_00` for 1:00:00 PM. Compare with [`Clock::seconds_since_midnight`]
+    ///instead, the same time gives different values in the two hour formats.
+    pub fn packed_bcd(&self) -> u32 {
+        u32::from(u8::from(self.hours)) << 16
+            | u32::from(decimal_to_bcd(self.minutes)) << 8
+            | u32::from(decimal_to_bcd(self.seconds))
+    }
+
+    ///Unpack a clock from the layout of [`Clock::packed_bcd`], the hour format is kept
+    ///
+    ///Fails with `Ds1302Error::Parameter` if bits 31..=24 aren't 0, a field isn't valid BCD or
+    ///a value is out of range.
+    pub fn from_packed_bcd(packed: u32) -> Result<Self, Ds1302Error> {
+        let [top, hours, minutes, seconds] = packed.to_be_bytes();
+        if top != 0 || bcd_to_decimal_checked(hours_bcd(hours)).is_none() {
+            return Err(Ds1302Error::Parameter);
+        }
+        let minutes = bcd_to_decimal_checked(minutes).ok_or(Ds1302Error::Parameter)?;
+        let seconds = bcd_to_decimal_checked(seconds).ok_or(Ds1302Error::Parameter)?;
+        Clock::new(Hours::from(hours), minutes, seconds)
+    }
+
+    ///Seconds since midnight, in either hour format
+    pub fn seconds_since_midnight(&self) -> u32 {
+        u32::from(self.hours.hour24()) * 3600
+            + u32::from(self.minutes) * 60
+            + u32::from(self.seconds)
+    }
+
+    // valid hours, minutes and seconds 0..=59
+    fn is_valid(&self) -> bool {
+        self.hours.is_valid() && self.minutes <= 59 && self.seconds <= 59
+    }
+}
+
+impl PartialEq for Clock {
+    fn eq(&self, other: &Self) -> bool {
+        self.seconds_since_midnight() == other.seconds_since_midnight()
+    }
+}
+
+impl Eq for Clock {}
+
+impl PartialOrd for Clock {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Clock {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.seconds_since_midnight()
+            .cmp(&other.seconds_since_midnight())
+    }
+}
+
+///Zero-padded `HH:MM:SS`, 12-hour clocks are followed by ` AM` or ` PM`
+impl core::fmt::Display for Clock {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (hours, suffix) = self.hours.display_parts();
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}",
+            hours, self.minutes, self.seconds, suffix
+        )
+    }
+}
+
 ///Calendar information
+///
+///The fields are public, [`Calendar::new`] checks the values before they reach the chip.
+///Calendars are ordered by year, month and date. The day of the week only breaks ties between
+///calendars of the same date, so that the order agrees with `==`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Calendar {
     pub day: u8,
     pub date: u8,
@@ -158,345 +572,556 @@ pub struct Calendar {
     pub year: u16,
 }
 
-mod registers;
+impl PartialOrd for Calendar {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-impl<SPI, CS, E, PinError, CLK, const TIMER_HZ: u32> DS1302<SPI, CS, CLK, TIMER_HZ>
-where
-    SPI: spi::Transfer<u8, Error = E> + spi::Write<u8, Error = E>,
-    CS: OutputPin<Error = PinError>,
-    CLK: Delay<TIMER_HZ>,
-{
-    ///Creates new instance DS1302 RTC
-    pub fn new(spi: SPI, cs: CS, mode: Mode, timer: CLK) -> Result<Self, Ds1302Error> {
-        let mut ds1302 = DS1302 { spi, cs, timer };
-        // Check CLOCK HALT FLAG bit
-        let byte = ds1302.read_reg(Register::SECONDS.addr())?;
-        // Reset CLOCK HALT FLAG bit, power on device
-        if (byte & CLOCK_HALT_FLAG) != 0 {
-            ds1302.write_reg(Register::SECONDS.addr(), 0)?;
-            let byte = ds1302.read_reg(Register::SECONDS.addr())?;
-            if (byte & CLOCK_HALT_FLAG) != 0 {
-                Err(Ds1302Error::Unknown)
-            } else {
-                ds1302.set_clock_mode(mode)?;
-                Ok(ds1302)
-            }
-        } else {
-            ds1302.set_clock_mode(mode)?;
-            Ok(ds1302)
+impl Ord for Calendar {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.year, self.month, self.date, self.day).cmp(&(
+            other.year,
+            other.month,
+            other.date,
+            other.day,
+        ))
+    }
+}
+
+impl Calendar {
+    ///Create a calendar, fails with `Ds1302Error::Parameter` if a value is out of range
+    ///
+    ///`day` is 1..=7, `date` must exist in the `month` and `year` must be 2000..=2099.
+    pub fn new(day: u8, date: u8, month: u8, year: u16) -> Result<Self, Ds1302Error> {
+        let calendar = Calendar {
+            day,
+            date,
+            month,
+            year,
+        };
+        if !(2000..=2099).contains(&year) || !calendar.is_valid() {
+            return Err(Ds1302Error::Parameter);
         }
+        Ok(calendar)
     }
-    ///Delete DS1302 RTC instance and return SPI interface and cs PIN
-    pub fn destroy(self) -> Result<(SPI, CS, CLK), Ds1302Error> {
-        Ok((self.spi, self.cs, self.timer))
-    }
-
-    fn read_reg(&mut self, reg: u8) -> Result<u8, Ds1302Error> {
-        let mut bytes = [reg | READ_BIT, 0];
-        nb::block!(self.timer.wait()).ok(); // wait CE inactive time min 4us
-        self.cs.set_high().ok();
-        self.spi
-            .transfer(&mut bytes)
-            .map_err(|_| Ds1302Error::Spi)?;
-        self.cs.set_low().ok();
-        self.timer.start(4.micros()).ok();
-        Ok(bytes[1])
-    }
-
-    fn write_reg(&mut self, reg: u8, byte: u8) -> Result<(), Ds1302Error> {
-        //Firstly Check WRITE_PROTECT_BIT
-        let wp_read = self.read_reg(Register::WP.addr())?;
-        if (wp_read & WRITE_PROTECT_BIT) != 0 {
-            let mut bytes = [Register::WP.addr(), 0];
-            nb::block!(self.timer.wait()).ok(); // wait CE inactive time min 4us
-            self.cs.set_high().ok();
-            self.spi.write(&mut bytes).map_err(|_| Ds1302Error::Spi)?;
-            self.cs.set_low().ok();
-            self.timer.start(4.micros()).ok();
-        }
-        //Then write current data to registers
-        let mut bytes = [reg, byte];
-        nb::block!(self.timer.wait()).ok(); // wait CE inactive time min 4us
-        self.cs.set_high().ok();
-        self.spi.write(&mut bytes).map_err(|_| Ds1302Error::Spi)?;
-        self.cs.set_low().ok();
-        self.timer.start(4.micros()).ok();
-        Ok(())
+
+    ///Day of the year, 1..=366 in leap years
+    ///
+    ///Computed from `date`, `month` and `year`, which must be a valid date.
+    pub fn day_of_year(&self) -> u16 {
+        day_of_year(self.year, self.month, self.date)
     }
 
-    ///Return current information about seconds
-    pub fn get_seconds(&mut self) -> Result<u8, Ds1302Error> {
-        self.read_reg(Register::SECONDS.addr())
-            .map(|b| bcd_to_decimal(b))
-    }
-    ///Return current information about minutes
-    pub fn get_minutes(&mut self) -> Result<u8, Ds1302Error> {
-        self.read_reg(Register::MINUTES.addr())
-            .map(|b| bcd_to_decimal(b))
-    }
-    ///Return current information about hours
-    pub fn get_hours(&mut self) -> Result<Hours, Ds1302Error> {
-        self.read_reg(Register::HOURS.addr()).map(|b| b.into())
-    }
-    ///Return current information about date
-    pub fn get_date(&mut self) -> Result<u8, Ds1302Error> {
-        self.read_reg(Register::DATE.addr())
-            .map(|b| bcd_to_decimal(b))
-    }
-    ///Return current information about month
-    pub fn get_month(&mut self) -> Result<u8, Ds1302Error> {
-        self.read_reg(Register::MONTH.addr())
-            .map(|b| bcd_to_decimal(b))
-    }
-    ///Return current information about year
-    pub fn get_year(&mut self) -> Result<u16, Ds1302Error> {
-        self.read_reg(Register::YEAR.addr())
-            .map(|b| 2000_u16 + (bcd_to_decimal(b) as u16))
-    }
-    ///Return current information about day of the week
-    pub fn get_day(&mut self) -> Result<u8, Ds1302Error> {
-        self.read_reg(Register::DAY.addr())
-            .map(|b| bcd_to_decimal(b))
-    }
-    ///Return current information about hours, minutes and seconds
-    pub fn get_clock(&mut self) -> Result<Clock, Ds1302Error> {
-        let mut bytes = [0_u8; 4];
-        bytes[0] = Register::CLKBURS.addr() | 1_u8;
-        nb::block!(self.timer.wait()).ok(); // wait CE inactive time min 4us
-        self.cs.set_high().ok();
-        self.spi
-            .transfer(&mut bytes)
-            .map_err(|_| Ds1302Error::Spi)?;
-        self.cs.set_low().ok();
-        self.timer.start(4.micros()).ok();
+    ///ISO 8601 week number, 1..=53
+    ///
+    ///Weeks start on Monday and week 1 contains the first Thursday of the year, so the first
+    ///days of January can belong to the last week of the previous year and the last days of
+    ///December to week 1. The day of the week is computed from the date, the `day` field is
+    ///not used.
+    pub fn iso_week(&self) -> u8 {
+        iso_week(self.year, self.month, self.date)
+    }
 
-        let clock = Clock {
-            seconds: bcd_to_decimal(bytes[1]),
-            minutes: bcd_to_decimal(bytes[2]),
-            hours: bytes[3].into(),
-        };
+    // month 1..=12, date within the month, day 1..=7
+    fn is_valid(&self) -> bool {
+        match days_in_month(self.year, self.month) {
+            Some(days) => (1..=days).contains(&self.date) && (1..=7).contains(&self.day),
+            None => false,
+        }
+    }
+}
 
-        Ok(clock)
+///Zero-padded `YYYY-MM-DD`, the day of the week is not shown
+impl core::fmt::Display for Calendar {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.date)
     }
-    ///Return current information about date, day of the week, month and year
-    pub fn get_calendar(&mut self) -> Result<Calendar, Ds1302Error> {
-        let mut bytes = [0_u8; 8];
-        bytes[0] = Register::CLKBURS.addr() | 1_u8;
-        nb::block!(self.timer.wait()).ok(); // wait CE inactive time min 4us
-        self.cs.set_high().ok();
-        self.spi
-            .transfer(&mut bytes)
-            .map_err(|_| Ds1302Error::Spi)?;
-        self.cs.set_low().ok();
-        self.timer.start(4.micros()).ok();
+}
 
-        let calendar = Calendar {
-            date: bcd_to_decimal(bytes[4]),
-            day: bcd_to_decimal(bytes[5]),
-            month: bcd_to_decimal(bytes[6]),
-            year: (2000_u16 + (bcd_to_decimal(bytes[7]) as u16)),
-        };
+///Clock and calendar information, read and written in one burst
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DateTime {
+    pub clock: Clock,
+    pub calendar: Calendar,
+}
 
-        Ok(calendar)
+impl DateTime {
+    ///Whether all fields are in range: the time exists, the date exists in its month,
+    ///the day of the week is 1..=7 and the year is 2000..=2099
+    ///
+    ///A chip that lost its backup supply can return values like month 0 that fail this check.
+    pub fn is_valid(&self) -> bool {
+        self.clock.is_valid()
+            && self.calendar.is_valid()
+            && (2000..=2099).contains(&self.calendar.year)
     }
-    ///Return current information date and time
-    pub fn get_clock_calendar(&mut self) -> Result<(Clock, Calendar), Ds1302Error> {
-        let mut bytes = [0_u8; 8];
-        bytes[0] = Register::CLKBURS.addr() | 1_u8;
-        nb::block!(self.timer.wait()).ok(); // wait CE inactive time min 4us
-        self.cs.set_high().ok();
-        self.spi
-            .transfer(&mut bytes)
-            .map_err(|_| Ds1302Error::Spi)?;
-        self.cs.set_low().ok();
-        self.timer.start(4.micros()).ok();
 
-        let clock = Clock {
-            seconds: bcd_to_decimal(bytes[1]),
-            minutes: bcd_to_decimal(bytes[2]),
-            hours: bytes[3].into(),
-        };
+    ///Move the date and time `secs` seconds forward, rolling over minutes, hours, days, months
+    ///and years including leap days
+    ///
+    ///The hour format is kept and the day of the week advances by the number of days passed,
+    ///whatever day it counts as 1. The result is clamped to 2099-12-31 23:59:59, the last time
+    ///the chip can hold. Fails with `Ds1302Error::Parameter` and leaves `self` untouched if the
+    ///date and time aren't valid, see [`DateTime::is_valid`].
+    pub fn add_seconds(&mut self, secs: u32) -> Result<(), Ds1302Error> {
+        if !self.is_valid() {
+            return Err(Ds1302Error::Parameter);
+        }
+        let start = datetime_to_unix(self).ok_or(Ds1302Error::Parameter)?;
+        let end = (start + u64::from(secs)).min(LAST_UNIX_SECOND);
+        let mut datetime = unix_to_datetime(end).ok_or(Ds1302Error::Parameter)?;
+        if self.clock.hours.is_pm().is_some() {
+            datetime.clock.hours = datetime.clock.hours.convert();
+        }
+        let days = (end / 86_400 - start / 86_400) % 7;
+        datetime.calendar.day = (self.calendar.day - 1 + days as u8) % 7 + 1;
+        *self = datetime;
+        Ok(())
+    }
 
-        let calendar = Calendar {
-            date: bcd_to_decimal(bytes[4]),
-            month: bcd_to_decimal(bytes[5]),
-            day: bcd_to_decimal(bytes[6]),
-            year: (2000_u16 + (bcd_to_decimal(bytes[7]) as u16)),
-        };
+    ///Format as ISO 8601 `YYYY-MM-DDTHH:MM:SS` into `buf` without `core::fmt`
+    ///
+    ///The hours are written in 24-hour format and the day of the week is not part of the output.
+    ///Fails with `Ds1302Error::Parameter` if the time or the date doesn't exist.
+    pub fn write_iso8601<'a>(&self, buf: &'a mut [u8; 19]) -> Result<&'a str, Ds1302Error> {
+        let calendar = &self.calendar;
+        let date_is_valid = calendar.year <= 9999
+            && days_in_month(calendar.year, calendar.month)
+                .is_some_and(|days| (1..=days).contains(&calendar.date));
+        if !self.clock.is_valid() || !date_is_valid {
+            return Err(Ds1302Error::Parameter);
+        }
+        write_digits(&mut buf[0..4], calendar.year);
+        buf[4] = b'-';
+        write_digits(&mut buf[5..7], u16::from(calendar.month));
+        buf[7] = b'-';
+        write_digits(&mut buf[8..10], u16::from(calendar.date));
+        buf[10] = b'T';
+        write_digits(&mut buf[11..13], u16::from(self.clock.hours.hour24()));
+        buf[13] = b':';
+        write_digits(&mut buf[14..16], u16::from(self.clock.minutes));
+        buf[16] = b':';
+        write_digits(&mut buf[17..19], u16::from(self.clock.seconds));
+        core::str::from_utf8(buf).map_err(|_| Ds1302Error::Unknown)
+    }
+}
 
-        Ok((clock, calendar))
-    }
-    ///Set seconds to defined value
-    pub fn set_seconds(&mut self, seconds: u8) -> Result<(), Ds1302Error> {
-        self.write_reg(Register::SECONDS.addr(), decimal_to_bcd(seconds))
-    }
-    ///Set minutes to defined value
-    pub fn set_minutes(&mut self, minutes: u8) -> Result<(), Ds1302Error> {
-        self.write_reg(Register::MINUTES.addr(), decimal_to_bcd(minutes))
-    }
-    ///Set hours to defined value
-    pub fn set_hours(&mut self, hours: Hours) -> Result<(), Ds1302Error> {
-        self.write_reg(Register::HOURS.addr(), hours.into())
-    }
-    ///Set date to defined value
-    pub fn set_date(&mut self, date: u8) -> Result<(), Ds1302Error> {
-        self.write_reg(Register::DATE.addr(), decimal_to_bcd(date))
-    }
-    ///Set month to defined value
-    pub fn set_month(&mut self, month: u8) -> Result<(), Ds1302Error> {
-        self.write_reg(Register::MONTH.addr(), decimal_to_bcd(month))
-    }
-    ///Set day of the week to defined value
-    pub fn set_day(&mut self, day: u8) -> Result<(), Ds1302Error> {
-        self.write_reg(Register::DAY.addr(), decimal_to_bcd(day))
-    }
-    ///Set year to defined value
-    pub fn set_year(&mut self, year: u16) -> Result<(), Ds1302Error> {
-        let y = if year < 2000 { 0 } else { year - 2000 };
-        self.write_reg(Register::YEAR.addr(), decimal_to_bcd(y as u8))
-    }
-    ///Set clock to defined values
-    pub fn set_clock(&mut self, clock: Clock) -> Result<(), Ds1302Error> {
-        //Not burst mode, because it changes the calendar registers
-        self.set_hours(clock.hours)?;
-        self.set_minutes(clock.minutes)?;
-        self.set_seconds(clock.seconds)
-    }
-    ///Set calendar to defined values
-    pub fn set_calendar(&mut self, calendar: Calendar) -> Result<(), Ds1302Error> {
-        //Not burst mode, because it changes the clock registers
-        self.set_year(calendar.year)?;
-        self.set_month(calendar.month)?;
-        self.set_date(calendar.date)?;
-        self.set_day(calendar.day)
-    }
-    ///Set clock and calendar to defined values
-    pub fn set_clock_calendar(
-        &mut self,
-        clock: Clock,
-        calendar: Calendar,
-    ) -> Result<(), Ds1302Error> {
-        //Writing in burst mode, it changes all the clock and calendar registers
-        let mut bytes = [0_u8; 9];
-        bytes[0] = Register::CLKBURS.addr();
-        bytes[1] = decimal_to_bcd(clock.seconds);
-        bytes[2] = decimal_to_bcd(clock.minutes);
-        bytes[3] = clock.hours.into();
-        bytes[4] = decimal_to_bcd(calendar.date);
-        bytes[5] = decimal_to_bcd(calendar.month);
-        bytes[6] = decimal_to_bcd(calendar.day);
-        let y = if calendar.year < 2000 {
-            0
-        } else {
-            calendar.year - 2000
-        };
-        bytes[7] = decimal_to_bcd(y as u8);
+// Write `value` as zero-padded decimal digits filling `buf`
+fn write_digits(buf: &mut [u8], mut value: u16) {
+    for digit in buf.iter_mut().rev() {
+        *digit = b'0' + (value % 10) as u8;
+        value /= 10;
+    }
+}
 
-        nb::block!(self.timer.wait()).ok(); // wait CE inactive time min 4us
-        self.cs.set_high().ok();
-        self.spi.write(&mut bytes).map_err(|_| Ds1302Error::Spi)?;
-        self.cs.set_low().ok();
-        self.timer.start(4.micros()).ok();
-        Ok(())
+///Ordered by calendar, then clock
+impl PartialOrd for DateTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
-    ///Switch between 12-hour (AM/PM) and 24-hour mode
-    pub fn set_clock_mode(&mut self, mode: Mode) -> Result<(), Ds1302Error> {
-        let hr = self.get_hours()?; // save current hours data
-        match hr {
-            Hours::Hour24(_h) => {
-                if mode == Mode::Hour12 {
-                    self.set_hours(hr.convert())
-                } else {
-                    Ok(())
-                }
-            }
-            _ => {
-                if mode == Mode::Hour24 {
-                    self.set_hours(hr.convert())
-                } else {
-                    Ok(())
-                }
+}
+
+impl Ord for DateTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.calendar, self.clock).cmp(&(other.calendar, other.clock))
+    }
+}
+
+///Raw register values from [`DS1302::dump_registers`], printable for bug reports
+///
+///The bytes are seconds, minutes, hours, date, month, day, year, write protect and trickle charger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegisterDump(pub [u8; 9]);
+
+impl core::fmt::Display for RegisterDump {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        const NAMES: [&str; 9] = [
+            "SEC", "MIN", "HR", "DATE", "MONTH", "DAY", "YEAR", "WP", "TCS",
+        ];
+        for (i, (name, byte)) in NAMES.iter().zip(self.0.iter()).enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
             }
+            write!(f, "{}={:02X}", name, byte)?;
         }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+pub mod asynch;
+mod builder;
+#[cfg(feature = "chrono")]
+pub mod chrono;
+mod date;
+mod driver;
+pub mod interface;
+#[cfg(test)]
+mod mock;
+#[cfg(feature = "no-delay")]
+mod no_delay;
+mod ram;
+mod registers;
+#[cfg(feature = "std")]
+mod std_delay;
+#[cfg(feature = "embedded-storage")]
+mod storage;
+#[cfg(feature = "time")]
+pub mod time;
+#[cfg(feature = "ufmt")]
+mod ufmt;
+
+impl<SPI, CS, E, PinError, CLK, const TIMER_HZ: u32> DS1302<SpiInterface<SPI, CS>, CLK, TIMER_HZ>
+where
+    SPI: spi::Transfer<u8, Error = E> + spi::Write<u8, Error = E>,
+    CS: OutputPin<Error = PinError>,
+    CLK: Delay<TIMER_HZ>,
+{
+    ///Creates new instance DS1302 RTC
+    pub fn new(
+        spi: SPI,
+        cs: CS,
+        mode: Mode,
+        timer: CLK,
+    ) -> Result<Self, DriverError<SpiInterface<SPI, CS>, CLK, TIMER_HZ>> {
+        DS1302::with_interface(SpiInterface::new(spi, cs), mode, timer, BitOrder::LsbFirst)
+    }
+    ///Creates new instance DS1302 RTC on a SPI bus that sends MSB first, the bits are reversed in software
+    pub fn new_msb_first(
+        spi: SPI,
+        cs: CS,
+        mode: Mode,
+        timer: CLK,
+    ) -> Result<Self, DriverError<SpiInterface<SPI, CS>, CLK, TIMER_HZ>> {
+        DS1302::with_interface(SpiInterface::new(spi, cs), mode, timer, BitOrder::MsbFirst)
+    }
+    ///Creates new instance DS1302 RTC with a chip select pin of the given polarity
+    ///
+    ///[`DS1302::new`] drives the CE input of the chip directly, so it is active high.
+    ///[`CsPolarity::ActiveLow`] is for a CE driven through an inverting transistor.
+    pub fn new_with_cs_polarity(
+        spi: SPI,
+        cs: CS,
+        mode: Mode,
+        timer: CLK,
+        polarity: CsPolarity,
+    ) -> Result<Self, DriverError<SpiInterface<SPI, CS>, CLK, TIMER_HZ>> {
+        DS1302::with_interface(
+            SpiInterface::with_cs_polarity(spi, cs, polarity),
+            mode,
+            timer,
+            BitOrder::LsbFirst,
+        )
+    }
+    ///Creates new instance DS1302 RTC waiting `ce_delay` between two transactions
+    ///
+    ///The datasheet minimum of the CE inactive time is [`T_CE_INACTIVE_NS`], which is the default
+    ///of [`DS1302::new`].
+    ///A longer time can help with long wires or at extreme temperatures.
+    pub fn new_with_timing(
+        spi: SPI,
+        cs: CS,
+        mode: Mode,
+        timer: CLK,
+        ce_delay: fugit::TimerDurationU32<TIMER_HZ>,
+    ) -> Result<Self, DriverError<SpiInterface<SPI, CS>, CLK, TIMER_HZ>> {
+        DS1302::init(
+            SpiInterface::new(spi, cs),
+            mode,
+            timer,
+            BitOrder::LsbFirst,
+            ce_delay,
+            true,
+        )
     }
+    ///Creates new instance DS1302 RTC without starting the oscillator
+    ///
+    ///A halted clock stays halted, [`DS1302::resume`] starts it and [`DS1302::clock_was_halted`]
+    ///tells whether it was found halted. Only the hour format is set like in [`DS1302::new`].
+    pub fn new_preserve_state(
+        spi: SPI,
+        cs: CS,
+        mode: Mode,
+        timer: CLK,
+    ) -> Result<Self, DriverError<SpiInterface<SPI, CS>, CLK, TIMER_HZ>> {
+        DS1302::init(
+            SpiInterface::new(spi, cs),
+            mode,
+            timer,
+            BitOrder::LsbFirst,
+            (T_CE_INACTIVE_NS / 1_000).micros(),
+            false,
+        )
+    }
+    ///Delete DS1302 RTC instance and return SPI interface and cs PIN
+    ///
+    ///The running countdown of the timer is cancelled, an error of [`Delay::cancel`] is ignored
+    ///so that the peripherals are always returned.
+    pub fn destroy(mut self) -> Result<(SPI, CS, CLK), Ds1302Error<E, PinError>> {
+        let _ = self.timer.cancel();
+        let (spi, cs) = self.iface.release();
+        Ok((spi, cs, self.timer))
+    }
+}
 
-    /// Enable trickle-charge.
-    /// Ds (diode drop voltage 0.7 or 1.4)
-    /// Rs (2k or 4k or 8k)
-    /// The maximum current = (Vcc - Ds) / Rs.
-    pub fn tc_enable(&mut self, ds: Ds, rs: Rs) -> Result<(), Ds1302Error> {
-        self.write_reg(Register::TCS.addr(), TrickleCharger::enable(ds, rs))
+impl<'a, SPI, CS, E, PinError, CLK, const TIMER_HZ: u32>
+    DS1302<SharedSpiInterface<'a, SPI, CS>, CLK, TIMER_HZ>
+where
+    SPI: spi::Transfer<u8, Error = E> + spi::Write<u8, Error = E>,
+    CS: OutputPin<Error = PinError>,
+    CLK: Delay<TIMER_HZ>,
+{
+    ///Creates new instance DS1302 RTC on a SPI bus shared with other devices, see [`SharedSpiInterface`]
+    ///
+    ///Other devices usually need the bus MSB first, `BitOrder::MsbFirst` then reverses the bits
+    ///for the DS1302 in software.
+    pub fn new_shared(
+        spi: &'a RefCell<SPI>,
+        cs: CS,
+        mode: Mode,
+        timer: CLK,
+        bit_order: BitOrder,
+    ) -> Result<Self, DriverError<SharedSpiInterface<'a, SPI, CS>, CLK, TIMER_HZ>> {
+        DS1302::with_interface(SharedSpiInterface::new(spi, cs), mode, timer, bit_order)
+    }
+    ///Delete DS1302 RTC instance and return the cs PIN and the timer
+    ///
+    ///The running countdown of the timer is cancelled, an error of [`Delay::cancel`] is ignored.
+    pub fn destroy(mut self) -> (CS, CLK) {
+        let _ = self.timer.cancel();
+        (self.iface.release(), self.timer)
     }
+}
 
-    /// Disable trickle-charge.
-    pub fn tc_disable(&mut self) -> Result<(), Ds1302Error> {
-        self.write_reg(Register::TCS.addr(), TrickleCharger::disable())
+impl<SPI, E, CLK, const TIMER_HZ: u32> DS1302<SpiNoCsInterface<SPI>, CLK, TIMER_HZ>
+where
+    SPI: spi::Transfer<u8, Error = E> + spi::Write<u8, Error = E>,
+    CLK: Delay<TIMER_HZ>,
+{
+    ///Creates new instance DS1302 RTC on an `embedded-hal` 0.2 SPI bus that drives CE itself
+    ///
+    ///See [`SpiNoCsInterface`] for the requirements on the bus. With `embedded-hal` 1.0 use
+    ///`new_spi_device` instead.
+    pub fn new_no_cs(
+        spi: SPI,
+        mode: Mode,
+        timer: CLK,
+    ) -> Result<Self, DriverError<SpiNoCsInterface<SPI>, CLK, TIMER_HZ>> {
+        DS1302::with_interface(SpiNoCsInterface::new(spi), mode, timer, BitOrder::LsbFirst)
+    }
+    ///Delete DS1302 RTC instance and return the SPI bus and the timer
+    ///
+    ///The running countdown of the timer is cancelled, an error of [`Delay::cancel`] is ignored.
+    pub fn destroy(mut self) -> (SPI, CLK) {
+        let _ = self.timer.cancel();
+        (self.iface.release(), self.timer)
     }
+}
 
-    /// Get the configuration of the trickle-charge register.
-    pub fn tc_get(&mut self) -> Result<(bool, Option<Ds>, Option<Rs>), Ds1302Error> {
-        let v = self.read_reg(Register::TCS.addr())?;
-        Ok(TrickleCharger::from(v).get())
+#[cfg(feature = "eh1")]
+impl<SPI, CLK, const TIMER_HZ: u32> DS1302<SpiDeviceInterface<SPI>, CLK, TIMER_HZ>
+where
+    SPI: eh1::spi::SpiDevice<u8>,
+    CLK: Delay<TIMER_HZ>,
+{
+    ///Creates new instance DS1302 RTC on an `embedded-hal` 1.0 SPI device, which drives the CE pin
+    pub fn new_spi_device(
+        spi: SPI,
+        mode: Mode,
+        timer: CLK,
+    ) -> Result<Self, DriverError<SpiDeviceInterface<SPI>, CLK, TIMER_HZ>> {
+        DS1302::with_interface(
+            SpiDeviceInterface::new(spi),
+            mode,
+            timer,
+            BitOrder::LsbFirst,
+        )
     }
+    ///Delete DS1302 RTC instance and return SPI device
+    ///
+    ///The running countdown of the timer is cancelled, an error of [`Delay::cancel`] is ignored.
+    pub fn destroy(mut self) -> (SPI, CLK) {
+        let _ = self.timer.cancel();
+        (self.iface.release(), self.timer)
+    }
+}
 
-    /// Whether to enable charging.
-    pub fn tc_is_enabled(&mut self) -> Result<bool, Ds1302Error> {
-        let v = self.read_reg(Register::TCS.addr())?;
-        Ok(TrickleCharger::from(v).is_enabled())
+#[cfg(feature = "eh1")]
+impl<SPI, CS, CLK, const TIMER_HZ: u32> DS1302<SpiBusInterface<SPI, CS>, CLK, TIMER_HZ>
+where
+    SPI: eh1::spi::SpiBus<u8>,
+    CS: eh1::digital::OutputPin,
+    CLK: Delay<TIMER_HZ>,
+{
+    ///Creates new instance DS1302 RTC on an `embedded-hal` 1.0 SPI bus and CE pin
+    pub fn new_spi_bus(
+        spi: SPI,
+        cs: CS,
+        mode: Mode,
+        timer: CLK,
+    ) -> Result<Self, DriverError<SpiBusInterface<SPI, CS>, CLK, TIMER_HZ>> {
+        DS1302::with_interface(
+            SpiBusInterface::new(spi, cs),
+            mode,
+            timer,
+            BitOrder::LsbFirst,
+        )
+    }
+    ///Delete DS1302 RTC instance and return SPI bus and cs PIN
+    ///
+    ///The running countdown of the timer is cancelled, an error of [`Delay::cancel`] is ignored.
+    pub fn destroy(mut self) -> (SPI, CS, CLK) {
+        let _ = self.timer.cancel();
+        let (spi, cs) = self.iface.release();
+        (spi, cs, self.timer)
     }
+}
 
-    /// Read DS1302 internal RAM. The static RAM is 31 x 8 bytes, index 0..=30.
-    pub fn read_ram(&mut self, index: u8) -> Result<u8, Ds1302Error> {
-        if index > 30 {
-            return Err(Ds1302Error::Parameter);
-        }
-        self.read_reg(Register::RAM.addr() + index * 2)
+impl<IF, CLK, const TIMER_HZ: u32> DS1302<IF, CLK, TIMER_HZ>
+where
+    IF: Interface,
+    CLK: Delay<TIMER_HZ>,
+{
+    ///Creates new instance DS1302 RTC on any SPI [`Interface`] sending bytes in `bit_order`
+    pub fn with_interface(
+        iface: IF,
+        mode: Mode,
+        timer: CLK,
+        bit_order: BitOrder,
+    ) -> Result<Self, DriverError<IF, CLK, TIMER_HZ>> {
+        DS1302::init(
+            iface,
+            mode,
+            timer,
+            bit_order,
+            (T_CE_INACTIVE_NS / 1_000).micros(),
+            true,
+        )
     }
 
-    /// Write DS1302 internal RAM. The static RAM is 31 x 8 bytes, index 0..=31.
-    pub fn write_ram(&mut self, index: u8, value: u8) -> Result<(), Ds1302Error> {
-        if index > 30 {
-            return Err(Ds1302Error::Parameter);
+    fn init(
+        iface: IF,
+        mode: Mode,
+        timer: CLK,
+        bit_order: BitOrder,
+        ce_delay: fugit::TimerDurationU32<TIMER_HZ>,
+        start_clock: bool,
+    ) -> Result<Self, DriverError<IF, CLK, TIMER_HZ>> {
+        let mut ds1302 = DS1302 {
+            iface,
+            timer,
+            bit_order,
+            ce_delay,
+            write_protect: None,
+            clock_was_halted: false,
+            mode,
+        };
+        // Check CLOCK HALT FLAG bit
+        let byte = ds1302.read_reg(Register::SECONDS.addr())?;
+        ds1302.clock_was_halted = (byte & CLOCK_HALT_FLAG) != 0;
+        // Reset CLOCK HALT FLAG bit, power on device
+        if ds1302.clock_was_halted && start_clock {
+            ds1302.write_reg(Register::SECONDS.addr(), 0)?;
+            let byte = ds1302.read_reg(Register::SECONDS.addr())?;
+            if (byte & CLOCK_HALT_FLAG) != 0 {
+                return Err(Ds1302Error::Unknown);
+            }
         }
-        self.write_reg(Register::RAM.addr() + index * 2, value)
-    }
-
-    /// Read DS1302 internal RAM burst mode. Start at 0 index.
-    /// The length is determined by the buf, but cannot exceed 31.
-    pub fn read_ram_burst(&mut self, buf: &mut [u8]) -> Result<(), Ds1302Error> {
-        let mut bytes = [0_u8; 32];
-        bytes[0] = Register::RAMBURS.addr() | 1_u8;
-        nb::block!(self.timer.wait()).ok(); // wait CE inactive time min 4us
-        self.cs.set_high().ok();
-        self.spi
-            .transfer(&mut bytes[..(buf.len() + 1)])
-            .map_err(|_| Ds1302Error::Spi)?;
-        self.cs.set_low().ok();
-        self.timer.start(4.micros()).ok();
-        buf.copy_from_slice(&bytes[1..(buf.len() + 1)]);
+        ds1302.set_clock_mode(mode)?;
+        Ok(ds1302)
+    }
+
+    fn transfer(&mut self, bytes: &mut [u8]) -> Result<(), DriverError<IF, CLK, TIMER_HZ>> {
+        // The command byte is overwritten by the byte read while it is sent
+        #[cfg(feature = "log")]
+        let command = bytes[0];
+        self.bit_order.apply(bytes);
+        nb::block!(self.timer.wait()).map_err(Ds1302Error::Timer)?; // wait CE inactive time
+        self.iface
+            .transfer(bytes)
+            .map_err(Ds1302Error::with_timer)?;
+        self.timer
+            .start(self.ce_delay)
+            .map_err(Ds1302Error::Timer)?;
+        self.bit_order.apply(bytes);
+        #[cfg(feature = "log")]
+        log::trace!("ds1302 read {:#04x}: {:02x?}", command, &bytes[1..]);
+        Ok(())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), DriverError<IF, CLK, TIMER_HZ>> {
+        let mut buf = [0_u8; RAM_SIZE + 1];
+        let buf = &mut buf[..bytes.len()];
+        buf.copy_from_slice(bytes);
+        self.bit_order.apply(buf);
+        nb::block!(self.timer.wait()).map_err(Ds1302Error::Timer)?; // wait CE inactive time
+        self.iface.write(buf).map_err(Ds1302Error::with_timer)?;
+        self.timer
+            .start(self.ce_delay)
+            .map_err(Ds1302Error::Timer)?;
+        #[cfg(feature = "log")]
+        log::trace!("ds1302 write {:#04x}: {:02x?}", bytes[0], &bytes[1..]);
+        self.track_mode(bytes);
         Ok(())
     }
 
-    /// Write DS1302 internal RAM burst mode. Start at 0 index.
-    /// The length is determined by the buf, but cannot exceed 31.
-    pub fn write_ram_burst(&mut self, buf: &[u8]) -> Result<usize, Ds1302Error> {
-        let mut bytes = [0_u8; 32];
-        bytes[0] = Register::RAMBURS.addr();
-        let ll = buf.len();
-        let ll = if ll > 31 { 31 } else { ll };
-        bytes[1..(ll + 1)].copy_from_slice(&buf[..ll]);
+    driver_methods!(DriverError<IF, CLK, TIMER_HZ>, FnOnce);
+}
 
-        nb::block!(self.timer.wait()).ok(); // wait CE inactive time min 4us
-        self.cs.set_high().ok();
-        self.spi
-            .write(&mut bytes[..(ll + 1)])
-            .map_err(|_| Ds1302Error::Spi)?;
-        self.cs.set_low().ok();
-        self.timer.start(4.micros()).ok();
-        Ok(ll)
+// Decode the seconds, minutes and hours registers at the start of a clock burst
+fn decode_clock(bytes: &[u8]) -> Clock {
+    Clock {
+        seconds: bcd_to_decimal(bytes[0] & !CLOCK_HALT_FLAG),
+        minutes: bcd_to_decimal(bytes[1]),
+        hours: bytes[2].into(),
     }
 }
 
+// Decode the seconds, minutes, hours, date, month, day and year registers of a clock burst,
+// together with `decode_clock` the only place mapping the burst positions to the fields
+fn decode_burst(bytes: &[u8]) -> (Clock, Calendar) {
+    let clock = decode_clock(bytes);
+    let calendar = Calendar {
+        date: bcd_to_decimal(bytes[3]),
+        month: bcd_to_decimal(bytes[4]),
+        day: bcd_to_decimal(bytes[5]),
+        year: (2000_u16 + (bcd_to_decimal(bytes[6]) as u16)),
+    };
+    (clock, calendar)
+}
+
+// Build the clock burst write command from the raw seconds, minutes and hours of a clock burst
+// read and a new calendar, the write protect byte is left cleared
+fn splice_calendar(clock: &[u8], calendar: Calendar) -> [u8; 9] {
+    let mut bytes = [0_u8; 9];
+    bytes[0] = Command::burst_write().byte();
+    bytes[1..4].copy_from_slice(clock);
+    bytes[4] = decimal_to_bcd(calendar.date);
+    bytes[5] = decimal_to_bcd(calendar.month);
+    bytes[6] = decimal_to_bcd(calendar.day);
+    bytes[7] = decimal_to_bcd(calendar.year.saturating_sub(2000) as u8);
+    bytes
+}
+
+// Build the clock burst write command, the write protect byte is left cleared
+fn encode_clock_calendar(clock: Clock, calendar: Calendar) -> [u8; 9] {
+    let mut bytes = [0_u8; 9];
+    bytes[0] = Command::burst_write().byte();
+    bytes[1] = decimal_to_bcd(clock.seconds);
+    bytes[2] = decimal_to_bcd(clock.minutes);
+    bytes[3] = clock.hours.into();
+    bytes[4] = decimal_to_bcd(calendar.date);
+    bytes[5] = decimal_to_bcd(calendar.month);
+    bytes[6] = decimal_to_bcd(calendar.day);
+    let y = calendar.year.saturating_sub(2000);
+    bytes[7] = decimal_to_bcd(y as u8);
+    bytes
+}
+
 // Swap format from bcd to decmial
 fn bcd_to_decimal(bcd: u8) -> u8 {
     ((bcd & 0xF0) >> 4) * 10 + (bcd & 0x0F)
@@ -506,3 +1131,1963 @@ fn bcd_to_decimal(bcd: u8) -> u8 {
 fn decimal_to_bcd(decimal: u8) -> u8 {
     ((decimal / 10) << 4) + (decimal % 10)
 }
+
+///Convert a BCD byte to decimal, `None` if a nibble is greater than 9
+pub fn bcd_to_decimal_checked(bcd: u8) -> Option<u8> {
+    if (bcd >> 4) > 9 || (bcd & 0x0F) > 9 {
+        return None;
+    }
+    Some(bcd_to_decimal(bcd))
+}
+
+///Convert 0..=99 to a BCD byte, `None` for greater values
+pub fn decimal_to_bcd_checked(decimal: u8) -> Option<u8> {
+    if decimal > 99 {
+        return None;
+    }
+    Some(decimal_to_bcd(decimal))
+}
+
+// BCD digits of the hours register without the 12-hour and PM flags
+fn hours_bcd(byte: u8) -> u8 {
+    if byte & HOUR_12_BIT != 0 {
+        byte & !(HOUR_12_BIT | HOUR_PM_BIT)
+    } else {
+        byte
+    }
+}
+
+// Hours register `byte` switched to the hour format `mode`, midnight if it isn't a valid hour
+fn hours_in_mode(byte: u8, mode: Mode) -> Hours {
+    let hours = Hours::from(byte);
+    if bcd_to_decimal_checked(hours_bcd(byte)).is_none() || !hours.is_valid() {
+        return match mode {
+            Mode::Hour12 => Hours::Hour12am(12),
+            Mode::Hour24 => Hours::Hour24(0),
+        };
+    }
+    if Mode::of_hours_register(byte) == mode {
+        hours
+    } else {
+        hours.convert()
+    }
+}
+
+// Whether the 7 clock and calendar bytes of a burst are valid BCD, without the CH, 12-hour and PM flags
+fn clock_calendar_is_bcd(bytes: &[u8]) -> bool {
+    clock_is_bcd(bytes)
+        && bytes[3..7]
+            .iter()
+            .all(|&b| bcd_to_decimal_checked(b).is_some())
+}
+
+// Whether the seconds, minutes and hours registers at the start of a clock burst are valid BCD
+fn clock_is_bcd(bytes: &[u8]) -> bool {
+    [bytes[0] & !CLOCK_HALT_FLAG, bytes[1], hours_bcd(bytes[2])]
+        .iter()
+        .all(|&b| bcd_to_decimal_checked(b).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{
+        burst_read, fake_rtc, memory, ram_burst_read, read, rtc, write, write_bytes,
+    };
+
+    #[test]
+    fn get_calendar_and_get_clock_calendar_decode_the_same_burst() {
+        // Thursday 2024-02-29 12:34:56, the burst order is date, month, day, year
+        let regs = [0x56, 0x34, 0x12, 0x29, 0x02, 0x04, 0x24];
+        let (mut rtc, mut spi) = rtc(&[burst_read(&regs), burst_read(&regs)]);
+        let calendar = rtc.get_calendar().unwrap();
+        let (_, burst_calendar) = rtc.get_clock_calendar().unwrap();
+        assert_eq!(calendar, burst_calendar);
+        assert_eq!(calendar, Calendar::new(4, 29, 2, 2024).unwrap());
+        spi.done();
+    }
+
+    #[test]
+    fn clock_halt_flag_is_masked_from_the_seconds() {
+        let regs = [0x80 | 0x59, 0x34, 0x12, 0x29, 0x02, 0x04, 0x24];
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x80, 0x80 | 0x45),
+            burst_read(&regs[..3]),
+            burst_read(&regs),
+        ]);
+        assert_eq!(rtc.get_seconds().unwrap(), 45);
+        assert_eq!(rtc.get_clock().unwrap().seconds, 59);
+        assert_eq!(rtc.get_clock_calendar().unwrap().0.seconds, 59);
+        spi.done();
+    }
+
+    #[test]
+    fn twelve_hour_values_round_trip() {
+        // 24-hour value, 12-hour value and its hours register
+        let cases = [
+            (0, Hours::Hour12am(12), 0x92),
+            (1, Hours::Hour12am(1), 0x81),
+            (12, Hours::Hour12pm(12), 0xB2),
+            (23, Hours::Hour12pm(11), 0xB1),
+        ];
+        for &(hour, twelve, byte) in cases.iter() {
+            let converted = Hours::Hour24(hour).convert();
+            assert_eq!(converted.hour(), twelve.hour());
+            assert_eq!(converted.convert().hour(), (hour, None));
+            assert_eq!(u8::from(converted), byte);
+            assert_eq!(Hours::from(byte).hour(), twelve.hour());
+        }
+    }
+
+    fn rejected<T>(result: Result<T, crate::mock::Error>) -> bool {
+        matches!(result, Err(Ds1302Error::Parameter))
+    }
+
+    #[test]
+    fn setters_reject_out_of_range_values_without_traffic() {
+        let (mut rtc, mut spi) = rtc(&[]);
+        assert!(rejected(rtc.set_seconds(60)));
+        assert!(rejected(rtc.set_minutes(60)));
+        assert!(rejected(rtc.set_hours(Hours::Hour24(24))));
+        assert!(rejected(rtc.set_hours(Hours::Hour12am(0))));
+        assert!(rejected(rtc.set_hours(Hours::Hour12pm(13))));
+        assert!(rejected(rtc.set_date(0)));
+        assert!(rejected(rtc.set_date(32)));
+        assert!(rejected(rtc.set_month(0)));
+        assert!(rejected(rtc.set_month(13)));
+        assert!(rejected(rtc.set_day(0)));
+        assert!(rejected(rtc.set_day(8)));
+        spi.done();
+    }
+
+    #[test]
+    fn setters_accept_the_last_valid_values() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x80, 0x00),
+            read(0x8E, 0x00),
+            write(0x80, 0x59),
+            write(0x82, 0x59),
+            write(0x84, 0x23),
+            write(0x84, 0x81),
+            write(0x84, 0xB2),
+            read(0x88, 0x01),
+            read(0x8C, 0x24),
+            write(0x86, 0x31),
+            write(0x88, 0x12),
+            write(0x8A, 0x07),
+        ]);
+        rtc.set_seconds(59).unwrap();
+        rtc.set_minutes(59).unwrap();
+        rtc.set_hours(Hours::Hour24(23)).unwrap();
+        rtc.set_hours(Hours::Hour12am(1)).unwrap();
+        rtc.set_hours(Hours::Hour12pm(12)).unwrap();
+        rtc.set_date(31).unwrap();
+        rtc.set_month(12).unwrap();
+        rtc.set_day(7).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn set_clock_mode_resets_an_invalid_hours_register_to_midnight() {
+        // 0x3F decodes to hour 45, which has no 12-hour equivalent
+        let (mut rtc, mut spi) = rtc(&[read(0x84, 0x3F), read(0x8E, 0x00), write(0x84, 0x92)]);
+        rtc.set_clock_mode(Mode::Hour12).unwrap();
+        assert_eq!(rtc.mode(), Mode::Hour12);
+        spi.done();
+    }
+
+    #[test]
+    fn write_protect_is_written_read_and_cleared_again() {
+        let (mut rtc, mut spi) = rtc(&[
+            write(0x8E, 0x80),
+            read(0x8E, 0x80),
+            write(0x8E, 0x00),
+            write(0x82, 0x05),
+            write(0x8E, 0x00),
+            read(0x8E, 0x00),
+        ]);
+        rtc.set_write_protect(true).unwrap();
+        assert!(rtc.is_write_protected().unwrap());
+        // The next write clears the protection transparently
+        rtc.set_minutes(5).unwrap();
+        rtc.set_write_protect(false).unwrap();
+        assert!(!rtc.is_write_protected().unwrap());
+        spi.done();
+    }
+
+    #[test]
+    fn halt_and_resume_keep_the_seconds() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x80, 0x25),
+            read(0x8E, 0x00),
+            write(0x80, 0xA5),
+            read(0x80, 0xA5),
+            read(0x80, 0xA5),
+            read(0x80, 0xA5),
+            write(0x80, 0x25),
+            read(0x80, 0x25),
+        ]);
+        rtc.halt().unwrap();
+        assert_eq!(rtc.get_seconds().unwrap(), 25);
+        assert!(!rtc.is_running().unwrap());
+        rtc.resume().unwrap();
+        assert!(rtc.is_running().unwrap());
+        spi.done();
+    }
+
+    #[test]
+    fn set_seconds_keeps_the_halt_flag_and_set_seconds_and_halt_sets_it() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x80, 0x80),
+            read(0x8E, 0x00),
+            write(0x80, 0xB0),
+            read(0x80, 0x00),
+            write(0x80, 0x30),
+            write(0x80, 0x87),
+        ]);
+        // Halted clock stays halted
+        rtc.set_seconds(30).unwrap();
+        // Running clock keeps running
+        rtc.set_seconds(30).unwrap();
+        rtc.set_seconds_and_halt(7).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn msb_first_bus_sends_every_byte_reversed() {
+        use embedded_hal_mock::eh0::spi::{Mock, Transaction};
+        let rev = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| b.reverse_bits())
+                .collect::<std::vec::Vec<_>>()
+        };
+        let read =
+            |addr: u8, value: u8| Transaction::transfer(rev(&[addr | 1, 0]), rev(&[0, value]));
+        let mut spi = Mock::new(&[
+            read(0x80, 0x00),
+            read(0x84, 0x12),
+            read(0x80, 0x59),
+            read(0x8E, 0x00),
+            Transaction::write(rev(&[0x82, 0x30])),
+            Transaction::transfer(rev(&[0xBF, 0, 0, 0]), rev(&[0, 0x07, 0x30, 0x12])),
+        ]);
+        let iface = SpiNoCsInterface::new(spi.clone());
+        let mut rtc =
+            DS1302::with_interface(iface, Mode::Hour24, crate::mock::NoWait, BitOrder::MsbFirst)
+                .unwrap();
+        assert_eq!(rtc.get_seconds().unwrap(), 59);
+        rtc.set_minutes(30).unwrap();
+        let clock = rtc.get_clock().unwrap();
+        assert_eq!(
+            (clock.hours.hour(), clock.minutes, clock.seconds),
+            (Hours::Hour24(12).hour(), 30, 7)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn datetime_round_trips_through_the_burst_registers() {
+        let regs = [0x00, 0x24, 0xA7, 0x19, 0x11, 0x05, 0x21, 0x00];
+        let mut written = std::vec![0xBE];
+        written.extend_from_slice(&regs);
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x8E, 0x00),
+            embedded_hal_mock::eh0::spi::Transaction::write(written),
+            burst_read(&regs[..7]),
+        ]);
+        let datetime = DateTime {
+            clock: Clock::new(Hours::Hour12pm(7), 24, 0).unwrap(),
+            calendar: Calendar::new(5, 19, 11, 2021).unwrap(),
+        };
+        rtc.set_datetime(datetime).unwrap();
+        let read_back = rtc.get_datetime().unwrap();
+        assert_eq!(read_back, datetime);
+        assert_eq!(read_back.clock.hours.hour(), datetime.clock.hours.hour());
+        spi.done();
+    }
+
+    #[test]
+    fn set_calendar_autoday_writes_the_computed_weekday() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x8E, 0x00),
+            write(0x8C, 0x21),
+            write(0x88, 0x11),
+            write(0x86, 0x19),
+            write(0x8A, 0x05),
+        ]);
+        // The wrong day is replaced by Friday
+        let calendar = Calendar::new(1, 19, 11, 2021).unwrap();
+        rtc.set_calendar_autoday(calendar).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn set_calendar_rejects_days_missing_from_the_month() {
+        let (mut rtc, mut spi) = rtc(&[]);
+        assert!(rejected(rtc.set_calendar(Calendar {
+            day: 3,
+            date: 29,
+            month: 2,
+            year: 2023
+        })));
+        assert!(rejected(rtc.set_calendar(Calendar {
+            day: 4,
+            date: 30,
+            month: 2,
+            year: 2024
+        })));
+        assert!(rejected(rtc.set_calendar(Calendar {
+            day: 5,
+            date: 31,
+            month: 4,
+            year: 2021
+        })));
+        spi.done();
+    }
+
+    #[test]
+    fn set_date_checks_the_month_and_year_of_the_chip() {
+        let (mut rtc, mut spi) = rtc(&[
+            // February 2023
+            read(0x88, 0x02),
+            read(0x8C, 0x23),
+            read(0x88, 0x02),
+            read(0x8C, 0x23),
+            read(0x8E, 0x00),
+            write(0x86, 0x28),
+            // February 2024
+            read(0x88, 0x02),
+            read(0x8C, 0x24),
+            write(0x86, 0x29),
+            // April
+            read(0x88, 0x04),
+            read(0x8C, 0x24),
+        ]);
+        assert!(rejected(rtc.set_date(29)));
+        rtc.set_date(28).unwrap();
+        rtc.set_date(29).unwrap();
+        assert!(rejected(rtc.set_date(31)));
+        spi.done();
+    }
+
+    #[test]
+    fn set_from_unix_rejects_years_outside_the_chip_range_without_traffic() {
+        let (mut rtc, mut spi) = rtc(&[]);
+        assert!(rejected(rtc.set_from_unix(946_684_799)));
+        assert!(rejected(rtc.set_from_unix(4_102_444_800)));
+        spi.done();
+    }
+
+    #[test]
+    fn set_from_unix_and_get_unix_use_the_hour_format_of_the_chip() {
+        // 2024-02-29 12:00:00, a Thursday, in 12-hour format
+        let regs = [0x00, 0x00, 0xB2, 0x29, 0x02, 0x04, 0x24, 0x00];
+        let mut written = std::vec![0xBE];
+        written.extend_from_slice(&regs);
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x84, 0x81),
+            read(0x8E, 0x00),
+            embedded_hal_mock::eh0::spi::Transaction::write(written),
+            burst_read(&regs[..7]),
+        ]);
+        rtc.set_from_unix(1_709_208_000).unwrap();
+        assert_eq!(rtc.get_unix().unwrap(), 1_709_208_000);
+        spi.done();
+    }
+
+    #[test]
+    fn whole_ram_pattern_round_trips() {
+        use embedded_hal_mock::eh0::spi::Transaction;
+        let mut pattern = [0_u8; RAM_SIZE];
+        for (i, byte) in pattern.iter_mut().enumerate() {
+            *byte = 0xA5 ^ i as u8;
+        }
+        let mut written = std::vec![0xFE];
+        written.extend_from_slice(&pattern);
+        let mut command = std::vec![0_u8; RAM_SIZE + 1];
+        command[0] = 0xFF;
+        let mut response = std::vec![0];
+        response.extend_from_slice(&pattern);
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x8E, 0x00),
+            Transaction::write(written),
+            Transaction::transfer(command, response),
+        ]);
+        rtc.write_ram_all(&pattern).unwrap();
+        assert_eq!(rtc.read_ram_all().unwrap(), pattern);
+        spi.done();
+    }
+
+    #[test]
+    fn ram_bursts_reject_more_than_31_bytes() {
+        use embedded_hal_mock::eh0::spi::Transaction;
+        let mut written = std::vec![0xFE];
+        written.extend_from_slice(&[7; RAM_SIZE]);
+        let mut command = std::vec![0_u8; RAM_SIZE + 1];
+        command[0] = 0xFF;
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x8E, 0x00),
+            Transaction::write(written),
+            Transaction::transfer(command.clone(), command),
+        ]);
+        assert!(rejected(rtc.write_ram_burst(&[7; RAM_SIZE + 1])));
+        assert!(rejected(rtc.read_ram_burst(&mut [0; RAM_SIZE + 1])));
+        assert_eq!(rtc.write_ram_burst(&[7; RAM_SIZE]).unwrap(), RAM_SIZE);
+        rtc.read_ram_burst(&mut [0; RAM_SIZE]).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn ram_ranges_address_each_byte_and_stay_inside_the_ram() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x8E, 0x00),
+            // Indexes 10..=12 are the registers 0xD4, 0xD6 and 0xD8
+            write(0xD4, 1),
+            write(0xD6, 2),
+            write(0xD8, 3),
+            read(0xFA, 4),
+            read(0xFC, 5),
+        ]);
+        rtc.write_ram_range(10, &[1, 2, 3]).unwrap();
+        let mut buf = [0; 2];
+        rtc.read_ram_range(29, &mut buf).unwrap();
+        assert_eq!(buf, [4, 5]);
+        assert!(rejected(rtc.write_ram_range(29, &[0; 3])));
+        assert!(rejected(rtc.read_ram_range(31, &mut [0; 1])));
+        spi.done();
+    }
+
+    #[test]
+    fn last_ram_byte_is_index_30() {
+        let (mut rtc, mut spi) = rtc(&[read(0x8E, 0x00), write(0xFC, 0x42), read(0xFC, 0x42)]);
+        rtc.write_ram(30, 0x42).unwrap();
+        assert_eq!(rtc.read_ram(30).unwrap(), 0x42);
+        assert!(rejected(rtc.write_ram(31, 0x42)));
+        assert!(rejected(rtc.read_ram(31)));
+        spi.done();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips() {
+        for hours in [Hours::Hour24(19), Hours::Hour12am(12), Hours::Hour12pm(7)] {
+            let json = serde_json::to_string(&hours).unwrap();
+            let back: Hours = serde_json::from_str(&json).unwrap();
+            assert_eq!(back.hour(), hours.hour());
+        }
+        let datetime = DateTime {
+            clock: Clock::new(Hours::Hour12pm(7), 24, 0).unwrap(),
+            calendar: Calendar::new(5, 19, 11, 2021).unwrap(),
+        };
+        let json = serde_json::to_string(&datetime).unwrap();
+        assert_eq!(serde_json::from_str::<DateTime>(&json).unwrap(), datetime);
+        let json = serde_json::to_string(&Mode::Hour12).unwrap();
+        assert_eq!(serde_json::from_str::<Mode>(&json).unwrap(), Mode::Hour12);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_representation_of_hours_is_stable() {
+        assert_eq!(
+            serde_json::to_string(&Hours::Hour24(19)).unwrap(),
+            r#"{"Hour24":19}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Hours::Hour12am(12)).unwrap(),
+            r#"{"Hour12am":12}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Hours::Hour12pm(7)).unwrap(),
+            r#"{"Hour12pm":7}"#
+        );
+        let clock = Clock::new(Hours::Hour24(8), 5, 9).unwrap();
+        assert_eq!(
+            serde_json::to_string(&clock).unwrap(),
+            r#"{"hours":{"Hour24":8},"minutes":5,"seconds":9}"#
+        );
+    }
+
+    #[test]
+    fn hours_are_equal_when_they_are_the_same_hour_of_the_day() {
+        assert_eq!(Hours::Hour24(13), Hours::Hour12pm(1));
+        assert_eq!(Hours::Hour24(0), Hours::Hour12am(12));
+        assert_eq!(Hours::Hour24(12), Hours::Hour12pm(12));
+        assert_ne!(Hours::Hour12am(1), Hours::Hour12pm(1));
+        assert_ne!(Hours::Hour24(1), Hours::Hour12pm(1));
+    }
+
+    #[test]
+    fn value_types_are_copy_and_comparable() {
+        let calendar = Calendar::new(5, 19, 11, 2021).unwrap();
+        let copy = calendar;
+        assert_eq!(calendar, copy);
+        assert_ne!(
+            calendar,
+            Calendar {
+                date: 20,
+                ..calendar
+            }
+        );
+        assert_eq!(Mode::Hour12, Mode::Hour12);
+        assert_ne!(Ds::ONE07V, Ds::TWO14V);
+        assert_eq!(Rs::R2K, Rs::R2K);
+        assert_eq!(std::format!("{:?}", Mode::Hour24), "Hour24");
+    }
+
+    #[test]
+    fn weekday_is_the_day_register() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x8A, 0x07),
+            read(0x8E, 0x00),
+            write(0x8A, 0x01),
+            read(0x8A, 0x00),
+        ]);
+        assert_eq!(rtc.get_weekday().unwrap(), Weekday::Sunday);
+        rtc.set_weekday(Weekday::Monday).unwrap();
+        assert!(matches!(rtc.get_weekday(), Err(Ds1302Error::InvalidData)));
+        spi.done();
+    }
+
+    #[test]
+    fn raw_seconds_register_matches_get_seconds_after_masking() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x80, 0xA5),
+            read(0x80, 0xA5),
+            read(0x8E, 0x00),
+            write(0x90, 0xA5),
+        ]);
+        let raw = rtc.read_register(0x80).unwrap();
+        assert_eq!(
+            bcd_to_decimal(raw & !CLOCK_HALT_FLAG),
+            rtc.get_seconds().unwrap()
+        );
+        rtc.write_register(0x90, 0xA5).unwrap();
+        assert!(rejected(rtc.read_register(0x00)));
+        assert!(rejected(rtc.write_register(0x7E, 0)));
+        spi.done();
+    }
+
+    #[test]
+    fn write_protection_is_cleared_only_once() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x80, 0x00),
+            read(0x8E, 0x80),
+            write(0x8E, 0x00),
+            write(0x80, 0x10),
+            write(0x82, 0x20),
+            write(0x82, 0x21),
+        ]);
+        rtc.set_seconds(10).unwrap();
+        rtc.set_minutes(20).unwrap();
+        rtc.set_minutes(21).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn ce_inactive_time_is_the_configured_one() {
+        use crate::mock::{Pin, Recorder};
+        use embedded_hal_mock::eh0::spi::Mock;
+        let expected = [read(0x80, 0x00), read(0x84, 0x12)];
+
+        let mut spi = Mock::new(&expected);
+        let rtc = DS1302::new_with_timing(
+            spi.clone(),
+            Pin::default(),
+            Mode::Hour24,
+            Recorder::default(),
+            25.micros(),
+        )
+        .unwrap();
+        let (_, _, timer) = rtc.destroy().unwrap();
+        assert_eq!(timer.started, [25, 25]);
+        spi.done();
+
+        let mut spi = Mock::new(&expected);
+        let rtc = DS1302::new(
+            spi.clone(),
+            Pin::default(),
+            Mode::Hour24,
+            Recorder::default(),
+        )
+        .unwrap();
+        let (_, _, timer) = rtc.destroy().unwrap();
+        // T_CE_INACTIVE_NS
+        assert_eq!(timer.started, [4, 4]);
+        spi.done();
+    }
+
+    #[test]
+    fn timer_error_is_returned_before_any_transaction() {
+        use crate::mock::{FailingDelay, TimerFault};
+        use embedded_hal_mock::eh0::spi::Mock;
+        let mut spi = Mock::new(&[]);
+        let result = DS1302::new_no_cs(spi.clone(), Mode::Hour24, FailingDelay);
+        assert!(matches!(result, Err(Ds1302Error::Timer(TimerFault))));
+        spi.done();
+    }
+
+    #[test]
+    fn constructor_reports_and_restarts_a_halted_clock() {
+        use embedded_hal_mock::eh0::spi::Mock;
+        // Power-on state of the chip: halted with the write protection set
+        let mut spi = Mock::new(&[
+            read(0x80, 0x80),
+            read(0x8E, 0x80),
+            write(0x8E, 0x00),
+            write(0x80, 0x00),
+            read(0x80, 0x00),
+            read(0x84, 0x12),
+        ]);
+        let halted = DS1302::new_no_cs(spi.clone(), Mode::Hour24, crate::mock::NoWait).unwrap();
+        assert!(halted.clock_was_halted());
+        spi.done();
+
+        let (running, mut spi) = rtc(&[]);
+        assert!(!running.clock_was_halted());
+        spi.done();
+    }
+
+    #[test]
+    fn error_display_strings() {
+        use crate::mock::{BusFault, PinFault, TimerFault};
+        use std::string::ToString;
+        type Error = Ds1302Error<BusFault, PinFault, TimerFault>;
+        assert_eq!(Error::Parameter.to_string(), "invalid parameter");
+        assert_eq!(Error::Spi(BusFault).to_string(), "SPI bus error: BusFault");
+        assert_eq!(
+            Error::Pin(PinFault).to_string(),
+            "chip select pin error: PinFault"
+        );
+        assert_eq!(
+            Error::Timer(TimerFault).to_string(),
+            "timer error: TimerFault"
+        );
+        assert_eq!(
+            Error::InvalidData.to_string(),
+            "invalid data read from the chip"
+        );
+        assert_eq!(
+            Error::Verify.to_string(),
+            "read back value differs from the written one"
+        );
+        assert_eq!(Error::Unknown.to_string(), "unknown error");
+        let error: &dyn core::error::Error = &Error::Parameter;
+        assert!(error.source().is_none());
+    }
+
+    #[test]
+    fn clock_new_boundaries() {
+        assert!(Clock::new(Hours::Hour24(0), 0, 0).is_ok());
+        assert!(Clock::new(Hours::Hour24(23), 59, 59).is_ok());
+        assert!(Clock::new(Hours::Hour12am(12), 0, 0).is_ok());
+        assert!(Clock::new(Hours::Hour12pm(11), 59, 59).is_ok());
+        assert!(Clock::new(Hours::Hour24(24), 0, 0).is_err());
+        assert!(Clock::new(Hours::Hour12am(0), 0, 0).is_err());
+        assert!(Clock::new(Hours::Hour12pm(13), 0, 0).is_err());
+        assert!(Clock::new(Hours::Hour24(0), 60, 0).is_err());
+        assert!(Clock::new(Hours::Hour24(0), 0, 60).is_err());
+    }
+
+    #[test]
+    fn calendar_new_boundaries() {
+        assert!(Calendar::new(6, 1, 1, 2000).is_ok());
+        assert!(Calendar::new(4, 31, 12, 2099).is_ok());
+        assert!(Calendar::new(4, 29, 2, 2024).is_ok());
+        assert!(Calendar::new(3, 29, 2, 2023).is_err());
+        assert!(Calendar::new(5, 31, 6, 2021).is_err());
+        assert!(Calendar::new(5, 0, 6, 2021).is_err());
+        assert!(Calendar::new(5, 1, 0, 2021).is_err());
+        assert!(Calendar::new(5, 1, 13, 2021).is_err());
+        assert!(Calendar::new(0, 1, 1, 2021).is_err());
+        assert!(Calendar::new(8, 1, 1, 2021).is_err());
+        assert!(Calendar::new(5, 31, 12, 1999).is_err());
+        assert!(Calendar::new(5, 1, 1, 2100).is_err());
+    }
+
+    #[test]
+    fn hour24_is_the_same_in_both_formats() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x84, 0xA7),
+            read(0x84, 0x19),
+            read(0x84, 0x92),
+            read(0x84, 0x00),
+            // set_hour24 writes in the format of the chip
+            read(0x84, 0x81),
+            read(0x8E, 0x00),
+            write(0x84, 0xA7),
+            read(0x84, 0x01),
+            write(0x84, 0x19),
+        ]);
+        assert_eq!(rtc.get_hour24().unwrap(), 19);
+        assert_eq!(rtc.get_hour24().unwrap(), 19);
+        assert_eq!(rtc.get_hour24().unwrap(), 0);
+        assert_eq!(rtc.get_hour24().unwrap(), 0);
+        rtc.set_hour24(19).unwrap();
+        rtc.set_hour24(19).unwrap();
+        assert!(rejected(rtc.set_hour24(24)));
+        spi.done();
+    }
+
+    #[test]
+    fn get_mode_follows_set_clock_mode() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x84, 0x12),
+            read(0x8E, 0x00),
+            write(0x84, 0xB2),
+            read(0x84, 0xB2),
+            read(0x84, 0xB2),
+            write(0x84, 0x12),
+            read(0x84, 0x12),
+        ]);
+        rtc.set_clock_mode(Mode::Hour12).unwrap();
+        assert_eq!(rtc.get_mode().unwrap(), Mode::Hour12);
+        rtc.set_clock_mode(Mode::Hour24).unwrap();
+        assert_eq!(rtc.get_mode().unwrap(), Mode::Hour24);
+        spi.done();
+    }
+
+    #[test]
+    fn clocks_order_across_noon_in_12_hour_format() {
+        let before_noon = Clock::new(Hours::Hour12am(11), 59, 59).unwrap();
+        let noon = Clock::new(Hours::Hour12pm(12), 0, 0).unwrap();
+        let one_pm = Clock::new(Hours::Hour12pm(1), 0, 0).unwrap();
+        let midnight = Clock::new(Hours::Hour12am(12), 0, 0).unwrap();
+        assert!(midnight < before_noon);
+        assert!(before_noon < noon);
+        assert!(noon < one_pm);
+        assert!(one_pm < Clock::new(Hours::Hour24(13), 0, 1).unwrap());
+        assert!(Hours::Hour12am(12) < Hours::Hour12am(1));
+        assert!(Hours::Hour12pm(12) < Hours::Hour12pm(1));
+    }
+
+    #[test]
+    fn calendars_order_across_month_and_year_ends() {
+        let new_years_eve = Calendar::new(5, 31, 12, 2021).unwrap();
+        let new_year = Calendar::new(6, 1, 1, 2022).unwrap();
+        let end_of_january = Calendar::new(1, 31, 1, 2022).unwrap();
+        let february = Calendar::new(2, 1, 2, 2022).unwrap();
+        assert!(new_years_eve < new_year);
+        assert!(end_of_january < february);
+        // The day of the week only breaks the tie of the same date
+        let wrong_day = Calendar { day: 7, ..new_year };
+        assert!(new_year < wrong_day);
+        assert!(wrong_day < Calendar::new(7, 2, 1, 2022).unwrap());
+
+        let late = DateTime {
+            clock: Clock::new(Hours::Hour24(23), 59, 59).unwrap(),
+            calendar: new_years_eve,
+        };
+        let early = DateTime {
+            clock: Clock::new(Hours::Hour24(0), 0, 0).unwrap(),
+            calendar: new_year,
+        };
+        assert!(late < early);
+    }
+
+    #[test]
+    fn seconds_since_midnight_in_both_formats() {
+        let cases = [
+            (Hours::Hour24(0), Hours::Hour12am(12), 0, 0, 0),
+            (Hours::Hour24(12), Hours::Hour12pm(12), 0, 0, 43_200),
+            (Hours::Hour24(23), Hours::Hour12pm(11), 59, 59, 86_399),
+        ];
+        for (h24, h12, minutes, seconds, expected) in cases {
+            let clock24 = Clock::new(h24, minutes, seconds).unwrap();
+            let clock12 = Clock::new(h12, minutes, seconds).unwrap();
+            assert_eq!(clock24.seconds_since_midnight(), expected);
+            assert_eq!(clock12.seconds_since_midnight(), expected);
+            let back = Clock::from_seconds_since_midnight(expected).unwrap();
+            assert_eq!(back.hours.hour(), h24.hour());
+            assert_eq!((back.minutes, back.seconds), (minutes, seconds));
+        }
+        assert!(Clock::from_seconds_since_midnight(86_400).is_err());
+    }
+
+    #[test]
+    fn dump_registers_reads_the_clock_burst_and_the_trickle_charger() {
+        let regs = [0x56, 0x34, 0x12, 0x31, 0x12, 0x04, 0x20, 0x80];
+        let (mut rtc, mut spi) = rtc(&[burst_read(&regs), read(0x90, 0x5C)]);
+        let dump = rtc.dump_registers().unwrap();
+        assert_eq!(dump, [0x56, 0x34, 0x12, 0x31, 0x12, 0x04, 0x20, 0x80, 0x5C]);
+        assert_eq!(
+            std::format!("{}", RegisterDump(dump)),
+            "SEC=56 MIN=34 HR=12 DATE=31 MONTH=12 DAY=04 YEAR=20 WP=80 TCS=5C"
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn set_clock_clears_the_write_protection_once_for_three_writes() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x80, 0x80),
+            read(0x8E, 0x80),
+            write(0x8E, 0x00),
+            write(0x84, 0x19),
+            write(0x82, 0x24),
+            write(0x80, 0x85),
+            // Once cleared, only the halt flag is read before the writes
+            read(0x80, 0x00),
+            write(0x84, 0x19),
+            write(0x82, 0x25),
+            write(0x80, 0x00),
+        ]);
+        rtc.set_clock(Clock::new(Hours::Hour24(19), 24, 5).unwrap())
+            .unwrap();
+        rtc.set_clock(Clock::new(Hours::Hour24(19), 25, 0).unwrap())
+            .unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn set_calendar_never_addresses_the_clock_registers() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x8E, 0x80),
+            write(0x8E, 0x00),
+            write(0x8C, 0x21),
+            write(0x88, 0x11),
+            write(0x86, 0x19),
+            write(0x8A, 0x05),
+        ]);
+        rtc.set_calendar(Calendar::new(5, 19, 11, 2021).unwrap())
+            .unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn set_calendar_rejects_years_the_chip_cannot_store() {
+        let (mut rtc, mut spi) = rtc(&[]);
+        for year in [1999, 2100, 2155] {
+            let calendar = Calendar {
+                day: 1,
+                date: 1,
+                month: 1,
+                year,
+            };
+            assert!(rejected(rtc.set_calendar(calendar)));
+            assert!(rejected(rtc.set_calendar_autoday(calendar)));
+        }
+        spi.done();
+    }
+
+    #[test]
+    fn get_datetime_checked_detects_out_of_range_registers() {
+        let (mut rtc, mut spi) = rtc(&[
+            // Month 0 and date 0 after a power loss
+            burst_read(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00]),
+            // Minutes 60
+            burst_read(&[0x00, 0x60, 0x12, 0x01, 0x01, 0x06, 0x00]),
+            // February 30th
+            burst_read(&[0x00, 0x00, 0x12, 0x30, 0x02, 0x06, 0x21]),
+            // Seconds nibble 0xA
+            burst_read(&[0x5A, 0x00, 0x12, 0x28, 0x02, 0x07, 0x21]),
+            burst_read(&[0x00, 0x00, 0x12, 0x28, 0x02, 0x07, 0x21]),
+        ]);
+        for _ in 0..4 {
+            assert!(matches!(
+                rtc.get_datetime_checked(),
+                Err(Ds1302Error::InvalidData)
+            ));
+        }
+        let datetime = rtc.get_datetime_checked().unwrap();
+        assert!(datetime.is_valid());
+        assert_eq!(datetime.calendar, Calendar::new(7, 28, 2, 2021).unwrap());
+        spi.done();
+    }
+
+    #[test]
+    fn datetime_is_valid_checks_every_field() {
+        let valid = DateTime {
+            clock: Clock::new(Hours::Hour24(23), 59, 59).unwrap(),
+            calendar: Calendar::new(4, 29, 2, 2024).unwrap(),
+        };
+        assert!(valid.is_valid());
+        let mut invalid = valid;
+        invalid.calendar.year = 2023;
+        assert!(!invalid.is_valid());
+        let mut invalid = valid;
+        invalid.clock.seconds = 60;
+        assert!(!invalid.is_valid());
+        let mut invalid = valid;
+        invalid.calendar.day = 0;
+        assert!(!invalid.is_valid());
+    }
+
+    #[test]
+    fn verify_ram_detects_a_corrupted_byte() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        rtc.write_ram_burst(&[1, 2, 3, 4, 5]).unwrap();
+        let crc = rtc.update_ram_crc(0..5).unwrap();
+        assert_eq!(memory.borrow()[32 + 5], crc);
+        assert!(rtc.verify_ram(0..5).unwrap());
+
+        memory.borrow_mut()[32 + 2] ^= 0x10;
+        assert!(!rtc.verify_ram(0..5).unwrap());
+    }
+
+    #[test]
+    fn verify_ram_detects_a_corrupted_crc() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        rtc.write_ram_burst(&[0xAA; 8]).unwrap();
+        rtc.update_ram_crc(0..8).unwrap();
+        memory.borrow_mut()[32 + 8] ^= 0x01;
+        assert!(!rtc.verify_ram(0..8).unwrap());
+    }
+
+    #[test]
+    fn ram_crc_rejects_a_region_without_room_for_the_crc() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        assert!(matches!(
+            rtc.update_ram_crc(0..31),
+            Err(Ds1302Error::Parameter)
+        ));
+        assert!(matches!(rtc.verify_ram(0..31), Err(Ds1302Error::Parameter)));
+    }
+
+    #[test]
+    fn every_year_setter_keeps_to_2000_through_2099() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        let clock = Clock::new(Hours::Hour24(12), 0, 0).unwrap();
+        for (year, bcd) in [(2000, 0x00), (2099, 0x99)] {
+            let calendar = Calendar::new(6, 1, 1, year).unwrap();
+            rtc.set_year(year).unwrap();
+            assert_eq!(rtc.get_year().unwrap(), year);
+            rtc.set_calendar_burst(calendar).unwrap();
+            assert_eq!(memory.borrow()[6], bcd);
+            rtc.set_clock_calendar(clock, calendar).unwrap();
+            assert_eq!(rtc.get_datetime().unwrap().calendar.year, year);
+        }
+
+        let before = *memory.borrow();
+        for year in [1999, 2100] {
+            let calendar = Calendar {
+                day: 1,
+                date: 1,
+                month: 1,
+                year,
+            };
+            let parameter = |result: Result<(), _>| matches!(result, Err(Ds1302Error::Parameter));
+            assert!(parameter(rtc.set_year(year)));
+            assert!(parameter(rtc.set_calendar_burst(calendar)));
+            assert!(parameter(rtc.set_clock_calendar(clock, calendar)));
+            assert!(parameter(rtc.set_datetime(DateTime { clock, calendar })));
+            assert!(parameter(rtc.set_datetime_autoday(clock, year, 1, 1)));
+        }
+        assert_eq!(*memory.borrow(), before);
+    }
+
+    #[test]
+    fn checked_bcd_conversions_over_every_byte() {
+        for byte in 0..=255_u8 {
+            let (high, low) = (byte >> 4, byte & 0x0F);
+            let expected = (high <= 9 && low <= 9).then(|| high * 10 + low);
+            assert_eq!(bcd_to_decimal_checked(byte), expected, "{byte:#04x}");
+
+            let expected = (byte <= 99).then_some(((byte / 10) << 4) | (byte % 10));
+            assert_eq!(decimal_to_bcd_checked(byte), expected, "{byte}");
+        }
+    }
+
+    #[test]
+    fn with_writes_enabled_clears_the_protection_once_and_restores_it() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x8E, 0x80),
+            write(0x8E, 0x00),
+            write(0x82, 0x34),
+            read(0x80, 0x12),
+            write(0x80, 0x56),
+            write(0x8E, 0x80),
+        ]);
+        rtc.with_writes_enabled(|rtc| {
+            rtc.set_minutes(34)?;
+            rtc.set_seconds(56)
+        })
+        .unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn with_writes_enabled_restores_the_protection_when_the_closure_fails() {
+        let (mut rtc, mut spi) = rtc(&[read(0x8E, 0x80), write(0x8E, 0x00), write(0x8E, 0x80)]);
+        let result = rtc.with_writes_enabled(|rtc| rtc.set_minutes(60));
+        assert!(rejected(result));
+        spi.done();
+    }
+
+    #[test]
+    fn with_writes_enabled_leaves_an_unprotected_chip_unprotected() {
+        let (mut rtc, mut spi) = rtc(&[read(0x8E, 0x00), write(0x82, 0x34)]);
+        rtc.with_writes_enabled(|rtc| rtc.set_minutes(34)).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn hours_from_24_accepts_0_through_23() {
+        for hour in [0, 12, 13, 23] {
+            assert!(matches!(Hours::from_24(hour), Ok(Hours::Hour24(h)) if h == hour));
+        }
+        assert!(matches!(Hours::from_24(24), Err(Ds1302Error::Parameter)));
+    }
+
+    #[test]
+    fn hours_from_12_accepts_1_through_12() {
+        assert!(matches!(Hours::from_12(1, false), Ok(Hours::Hour12am(1))));
+        assert!(matches!(Hours::from_12(12, false), Ok(Hours::Hour12am(12))));
+        assert!(matches!(Hours::from_12(12, true), Ok(Hours::Hour12pm(12))));
+        assert_eq!(Hours::from_12(12, false).unwrap().hour24(), 0);
+        assert_eq!(Hours::from_12(12, true).unwrap().hour24(), 12);
+        assert_eq!(Hours::from_12(1, true).unwrap().hour24(), 13);
+        for pm in [false, true] {
+            assert!(matches!(Hours::from_12(0, pm), Err(Ds1302Error::Parameter)));
+            assert!(matches!(
+                Hours::from_12(13, pm),
+                Err(Ds1302Error::Parameter)
+            ));
+        }
+    }
+
+    #[test]
+    fn ram_transaction_batches_the_changes_into_one_burst() {
+        let mut ram = [0_u8; RAM_SIZE];
+        ram[1] = 0x11;
+        // One burst read, the write protect check and one burst write up to the last change
+        let (mut rtc, mut spi) = rtc(&[
+            ram_burst_read(&ram),
+            read(0x8E, 0x00),
+            write_bytes(&[0xFE, 0xAA, 0x11, 0, 0, 0, 0xBB]),
+        ]);
+        let value = rtc
+            .ram_transaction(|ram| {
+                ram.set(0, 0xAA)?;
+                ram.set(5, 0xBB)?;
+                ram.get(1)
+            })
+            .unwrap();
+        assert_eq!(value, 0x11);
+        spi.done();
+    }
+
+    #[test]
+    fn ram_transaction_writes_nothing_without_changes_or_on_error() {
+        let ram = [0_u8; RAM_SIZE];
+        let (mut rtc, mut spi) = rtc(&[ram_burst_read(&ram), ram_burst_read(&ram)]);
+        rtc.ram_transaction(|ram| ram.get(30)).unwrap();
+        let result = rtc.ram_transaction(|ram| {
+            ram.set(0, 1)?;
+            ram.set(31, 1)
+        });
+        assert!(rejected(result));
+        spi.done();
+    }
+
+    #[test]
+    fn ram_transaction_leaves_the_final_contents_in_ram() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        rtc.write_ram_burst(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        rtc.ram_transaction(|ram| {
+            ram.set(2, 0x33)?;
+            ram.set(6, 0x77)
+        })
+        .unwrap();
+        assert_eq!(
+            rtc.read_ram_all().unwrap()[..9],
+            [1, 2, 0x33, 4, 5, 6, 0x77, 8, 0]
+        );
+    }
+
+    #[test]
+    fn get_clock_calendar_weekday_decodes_a_known_burst() {
+        let (mut rtc, mut spi) = rtc(&[
+            // Friday 2021-11-19 19:24:00, the burst order is date, month, day, year
+            burst_read(&[0x00, 0x24, 0x19, 0x19, 0x11, 0x05, 0x21]),
+            // Day 0 after a power loss
+            burst_read(&[0x00, 0x24, 0x19, 0x19, 0x11, 0x00, 0x21]),
+            burst_read(&[0x00, 0x24, 0x19, 0x19, 0x11, 0x08, 0x21]),
+        ]);
+        let (clock, calendar, weekday) = rtc.get_clock_calendar_weekday().unwrap();
+        assert_eq!(weekday, Weekday::Friday);
+        assert_eq!(clock, Clock::new(Hours::Hour24(19), 24, 0).unwrap());
+        assert_eq!(calendar, Calendar::new(5, 19, 11, 2021).unwrap());
+        for _ in 0..2 {
+            let result = rtc.get_clock_calendar_weekday();
+            assert!(matches!(result, Err(Ds1302Error::InvalidData)));
+        }
+        spi.done();
+    }
+
+    fn calendar(year: u16, month: u8, date: u8) -> Calendar {
+        let day = weekday(year, month, date).unwrap();
+        Calendar::new(day, date, month, year).unwrap()
+    }
+
+    #[test]
+    fn day_of_year_in_leap_and_common_years() {
+        assert_eq!(calendar(2023, 1, 1).day_of_year(), 1);
+        assert_eq!(calendar(2024, 1, 1).day_of_year(), 1);
+        assert_eq!(calendar(2023, 12, 31).day_of_year(), 365);
+        assert_eq!(calendar(2024, 12, 31).day_of_year(), 366);
+        assert_eq!(calendar(2021, 7, 15).day_of_year(), 196);
+        assert_eq!(calendar(2024, 3, 1).day_of_year(), 61);
+    }
+
+    #[test]
+    fn iso_week_across_year_ends() {
+        // Monday, week 1
+        assert_eq!(calendar(2024, 1, 1).iso_week(), 1);
+        // Tuesday, in week 1 of 2025
+        assert_eq!(calendar(2024, 12, 31).iso_week(), 1);
+        // Sunday, the end of week 52
+        assert_eq!(calendar(2023, 12, 31).iso_week(), 52);
+        // 2020 has 53 weeks, the last one ends on Sunday 2021-01-03
+        assert_eq!(calendar(2020, 12, 31).iso_week(), 53);
+        assert_eq!(calendar(2021, 1, 1).iso_week(), 53);
+        assert_eq!(calendar(2021, 1, 4).iso_week(), 1);
+        assert_eq!(calendar(2021, 7, 15).iso_week(), 28);
+    }
+
+    #[test]
+    fn new_preserve_state_never_writes_the_seconds_register() {
+        use crate::mock::{NoWait, Pin};
+        use embedded_hal_mock::eh0::spi::Mock;
+
+        // Halted at 5 seconds, the hour format already matches
+        let mut spi = Mock::new(&[read(0x80, 0x85), read(0x84, 0x12)]);
+        let rtc =
+            DS1302::new_preserve_state(spi.clone(), Pin::default(), Mode::Hour24, NoWait).unwrap();
+        assert!(rtc.clock_was_halted());
+        spi.done();
+
+        // Only the hours register is written to change the format, noon becomes 12 PM
+        let mut spi = Mock::new(&[
+            read(0x80, 0x85),
+            read(0x84, 0x12),
+            read(0x8E, 0x00),
+            write(0x84, 0xB2),
+        ]);
+        DS1302::new_preserve_state(spi.clone(), Pin::default(), Mode::Hour12, NoWait).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn constructor_skips_the_hours_write_when_the_mode_is_unchanged() {
+        use crate::mock::NoWait;
+        use embedded_hal_mock::eh0::spi::Mock;
+
+        // 7 PM, already in 12-hour format
+        let mut spi = Mock::new(&[read(0x80, 0x00), read(0x84, 0xA7), read(0x84, 0xA7)]);
+        let mut twelve = DS1302::new_no_cs(spi.clone(), Mode::Hour12, NoWait).unwrap();
+        twelve.set_clock_mode(Mode::Hour12).unwrap();
+        assert_eq!(twelve.mode(), Mode::Hour12);
+        spi.done();
+
+        let (mut twenty_four, mut spi) = rtc(&[read(0x84, 0x12)]);
+        twenty_four.set_clock_mode(Mode::Hour24).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn hours_as_a_number_of_the_day_and_pm_flag() {
+        let cases = [
+            (Hours::Hour24(0), 0, None),
+            (Hours::Hour24(12), 12, None),
+            (Hours::Hour24(23), 23, None),
+            // Midnight and noon
+            (Hours::Hour12am(12), 0, Some(false)),
+            (Hours::Hour12pm(12), 12, Some(true)),
+            (Hours::Hour12am(1), 1, Some(false)),
+            (Hours::Hour12am(11), 11, Some(false)),
+            (Hours::Hour12pm(1), 13, Some(true)),
+            (Hours::Hour12pm(11), 23, Some(true)),
+        ];
+        for (hours, hour, pm) in cases {
+            assert_eq!(u8::from(&hours), hour, "{hours:?}");
+            assert_eq!(hours.is_pm(), pm, "{hours:?}");
+        }
+    }
+
+    #[test]
+    fn exported_state_imports_into_another_chip() {
+        let source = memory();
+        {
+            let mut memory = source.borrow_mut();
+            // 2021-11-19 19:24:56, protected, charger on
+            memory[..9].copy_from_slice(&[0x56, 0x24, 0x19, 0x19, 0x11, 0x05, 0x21, 0x80, 0xA5]);
+            for (i, byte) in memory[32..63].iter_mut().enumerate() {
+                *byte = i as u8 * 3;
+            }
+        }
+        let state = fake_rtc(&source).export_state().unwrap();
+        assert_eq!(
+            state[..9],
+            [0x56, 0x24, 0x19, 0x19, 0x11, 0x05, 0x21, 0x80, 0xA5]
+        );
+        assert_eq!(state[9..], source.borrow()[32..63]);
+
+        // A protected chip holding other values
+        let target = memory();
+        target.borrow_mut()[..8].copy_from_slice(&[0, 0, 0x12, 1, 1, 1, 0, 0x80]);
+        let mut rtc = fake_rtc(&target);
+        rtc.import_state(&state).unwrap();
+        assert_eq!(rtc.export_state().unwrap(), state);
+        assert_eq!(*target.borrow(), *source.borrow());
+    }
+
+    #[test]
+    fn default_ce_delay_is_the_datasheet_minimum() {
+        use crate::mock::{FakeChip, Recorder};
+        use embedded_hal_mock::eh0::spi::Mock;
+
+        // tCWH of the datasheet, in ticks of the 1 MHz timer
+        let ticks = T_CE_INACTIVE_NS / 1_000;
+        assert_eq!(ticks, 4);
+
+        let mut spi = Mock::new(&[read(0x80, 0x00), read(0x84, 0x12)]);
+        let rtc = DS1302::new_no_cs(spi.clone(), Mode::Hour24, Recorder::default()).unwrap();
+        assert_eq!(rtc.destroy().1.started, [ticks, ticks]);
+        spi.done();
+
+        let memory = memory();
+        let rtc = DS1302Builder::new(Mode::Hour24)
+            .build_with_interface(FakeChip::new(&memory), Recorder::default())
+            .unwrap();
+        assert_eq!(rtc.timer.started, [ticks, ticks]);
+    }
+
+    #[test]
+    fn set_datetime_autoday_bursts_the_computed_weekday() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x8E, 0x00),
+            // Thursday 2024-02-29 12:34:56, the day byte is the 7th
+            write_bytes(&[0xBE, 0x56, 0x34, 0x12, 0x29, 0x02, 0x04, 0x24, 0x00]),
+        ]);
+        let clock = Clock::new(Hours::Hour24(12), 34, 56).unwrap();
+        rtc.set_datetime_autoday(clock, 2024, 2, 29).unwrap();
+        // February 29th of a common year, then minutes out of range
+        assert!(rejected(rtc.set_datetime_autoday(clock, 2023, 2, 29)));
+        let late = Clock {
+            minutes: 60,
+            ..clock
+        };
+        assert!(rejected(rtc.set_datetime_autoday(late, 2024, 2, 29)));
+        spi.done();
+    }
+
+    #[test]
+    fn getters_reject_impossible_bcd_and_raw_getters_return_it() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x80, 0x8F),
+            read(0x80, 0x8F),
+            read(0x82, 0x5A),
+            read(0x82, 0x5A),
+            // 12-hour format, hour digits 0x1C
+            read(0x84, 0x9C),
+            read(0x84, 0x9C),
+            read(0x86, 0xF1),
+            read(0x86, 0xF1),
+            read(0x88, 0x1F),
+            read(0x8C, 0xAA),
+            read(0x8C, 0xAA),
+            read(0x8A, 0x0B),
+        ]);
+        let invalid = |result: Result<u8, _>| matches!(result, Err(Ds1302Error::InvalidData));
+        assert!(invalid(rtc.get_seconds()));
+        assert_eq!(rtc.get_seconds_raw().unwrap(), 0x8F);
+        assert!(invalid(rtc.get_minutes()));
+        assert_eq!(rtc.get_minutes_raw().unwrap(), 0x5A);
+        assert!(matches!(rtc.get_hours(), Err(Ds1302Error::InvalidData)));
+        assert_eq!(rtc.get_hours_raw().unwrap(), 0x9C);
+        assert!(invalid(rtc.get_date()));
+        assert_eq!(rtc.get_date_raw().unwrap(), 0xF1);
+        assert!(invalid(rtc.get_month()));
+        assert!(matches!(rtc.get_year(), Err(Ds1302Error::InvalidData)));
+        assert_eq!(rtc.get_year_raw().unwrap(), 0xAA);
+        assert!(invalid(rtc.get_day()));
+        spi.done();
+    }
+
+    #[test]
+    fn ram_iter_yields_the_31_bytes_of_one_burst() {
+        let mut ram = [0_u8; RAM_SIZE];
+        for (i, byte) in ram.iter_mut().enumerate() {
+            *byte = 0xF0 - i as u8;
+        }
+        let (mut rtc, mut spi) = rtc(&[ram_burst_read(&ram)]);
+        let bytes = rtc.ram_iter().unwrap();
+        assert_eq!(bytes.len(), 31);
+        assert!(bytes.eq(ram.iter().copied()));
+        spi.done();
+    }
+
+    #[test]
+    fn get_time_consistent_takes_every_field_from_one_burst() {
+        // One second before 2022, the single-field getters would straddle the rollover
+        let (mut rtc, mut spi) = rtc(&[burst_read(&[0x59, 0x59, 0x23, 0x31, 0x12, 0x05, 0x21])]);
+        let datetime = rtc.get_time_consistent().unwrap();
+        assert_eq!(
+            datetime.clock,
+            Clock::new(Hours::Hour24(23), 59, 59).unwrap()
+        );
+        assert_eq!(datetime.calendar, Calendar::new(5, 31, 12, 2021).unwrap());
+        spi.done();
+    }
+
+    #[test]
+    fn initialized_marker_tells_cold_and_warm_boots_apart() {
+        const MAGIC: u32 = 0xC0FF_EE42;
+        let memory = memory();
+
+        // Cold boot: the marker is written
+        assert!(!fake_rtc(&memory)
+            .check_and_mark_initialized(MAGIC, 27)
+            .unwrap());
+        assert_eq!(memory.borrow()[59..63], [0x42, 0xEE, 0xFF, 0xC0]);
+
+        // Warm boot: a new driver on the battery-backed RAM finds it
+        let mut rtc = fake_rtc(&memory);
+        assert!(rtc.check_and_mark_initialized(MAGIC, 27).unwrap());
+        assert!(matches!(
+            rtc.check_and_mark_initialized(MAGIC, 28),
+            Err(Ds1302Error::Parameter)
+        ));
+
+        // The backup supply was lost
+        *memory.borrow_mut() = [0; 64];
+        assert!(!fake_rtc(&memory)
+            .check_and_mark_initialized(MAGIC, 27)
+            .unwrap());
+    }
+
+    #[test]
+    fn equivalent_12_and_24_hour_clocks_are_equal() {
+        let pairs = [
+            (Hours::Hour24(0), Hours::Hour12am(12)),
+            (Hours::Hour24(1), Hours::Hour12am(1)),
+            (Hours::Hour24(11), Hours::Hour12am(11)),
+            (Hours::Hour24(12), Hours::Hour12pm(12)),
+            (Hours::Hour24(13), Hours::Hour12pm(1)),
+            (Hours::Hour24(23), Hours::Hour12pm(11)),
+        ];
+        for (hours24, hours12) in pairs {
+            let clock24 = Clock::new(hours24, 30, 15).unwrap();
+            let clock12 = Clock::new(hours12, 30, 15).unwrap();
+            assert_eq!(clock24, clock12);
+            // The register bytes still differ
+            assert_ne!(u8::from(clock24.hours), u8::from(clock12.hours));
+        }
+        let midnight = Clock::new(Hours::Hour12am(12), 0, 0).unwrap();
+        let noon = Clock::new(Hours::Hour12pm(12), 0, 0).unwrap();
+        assert_ne!(midnight, noon);
+        assert_ne!(midnight, Clock::new(Hours::Hour24(0), 0, 1).unwrap());
+    }
+
+    #[test]
+    fn set_calendar_burst_preserves_the_clock_bytes() {
+        let (mut rtc, mut spi) = rtc(&[
+            // Halted at 7:24:56 PM in 12-hour format
+            burst_read(&[0xD6, 0x24, 0xA7]),
+            read(0x8E, 0x00),
+            write_bytes(&[0xBE, 0xD6, 0x24, 0xA7, 0x29, 0x02, 0x04, 0x24, 0x00]),
+        ]);
+        let calendar = Calendar::new(4, 29, 2, 2024).unwrap();
+        rtc.set_calendar_burst(calendar).unwrap();
+        spi.done();
+    }
+
+    #[test]
+    fn tc_raw_returns_the_register_unchanged() {
+        let (mut rtc, mut spi) = rtc(&[read(0x90, 0x5C), read(0x90, 0xA9)]);
+        assert_eq!(rtc.tc_raw().unwrap(), 0x5C);
+        assert_eq!(rtc.tc_raw().unwrap(), 0xA9);
+        spi.done();
+    }
+
+    #[test]
+    fn ram_bounds_follow_ram_size() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        let last = RAM_SIZE as u8 - 1;
+        rtc.write_ram(last, 0x5A).unwrap();
+        assert_eq!(rtc.read_ram(last).unwrap(), 0x5A);
+        assert_eq!(memory.borrow()[62], 0x5A);
+        assert!(matches!(
+            rtc.write_ram(RAM_SIZE as u8, 0),
+            Err(Ds1302Error::Parameter)
+        ));
+        assert!(matches!(
+            rtc.read_ram(RAM_SIZE as u8),
+            Err(Ds1302Error::Parameter)
+        ));
+    }
+
+    #[test]
+    fn raw_hours_round_trip_through_hours() {
+        let hours24 = (0..=23).map(decimal_to_bcd);
+        let am = (1..=12).map(|hour| decimal_to_bcd(hour) | HOUR_12_BIT);
+        let pm = (1..=12).map(|hour| decimal_to_bcd(hour) | HOUR_12_BIT | HOUR_PM_BIT);
+        for byte in hours24.chain(am).chain(pm) {
+            assert_eq!(u8::from(Hours::from(byte)), byte, "{byte:#04x}");
+        }
+
+        let (mut rtc, mut spi) = rtc(&[read(0x84, 0xB2)]);
+        let raw = rtc.get_hours_raw().unwrap();
+        assert_ne!(raw & HOUR_12_BIT, 0);
+        assert_ne!(raw & HOUR_PM_BIT, 0);
+        assert!(matches!(Hours::from(raw), Hours::Hour12pm(12)));
+        spi.done();
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[repr(C)]
+    struct Config {
+        interval: u32,
+        threshold: u16,
+        channel: u8,
+        flags: u8,
+    }
+
+    // Safety: `repr(C)` without padding, every bit pattern is a valid value
+    #[cfg(feature = "bytemuck")]
+    unsafe impl bytemuck::Zeroable for Config {}
+    #[cfg(feature = "bytemuck")]
+    unsafe impl bytemuck::Pod for Config {}
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn ram_struct_round_trip() {
+        let memory = memory();
+        let config = Config {
+            interval: 0x1234_5678,
+            threshold: 0xABCD,
+            channel: 7,
+            flags: 0x81,
+        };
+        fake_rtc(&memory).write_ram_struct(23, &config).unwrap();
+        // The RAM outlives the driver
+        let mut rtc = fake_rtc(&memory);
+        assert_eq!(rtc.read_ram_struct::<Config>(23).unwrap(), config);
+        assert!(matches!(
+            rtc.write_ram_struct(24, &config),
+            Err(Ds1302Error::Parameter)
+        ));
+        assert!(matches!(
+            rtc.read_ram_struct::<Config>(24),
+            Err(Ds1302Error::Parameter)
+        ));
+    }
+
+    #[test]
+    fn verified_ram_writes_detect_a_differing_read_back() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x8E, 0x00),
+            write(0xC4, 0x42),
+            read(0xC4, 0x42),
+            write(0xC4, 0x43),
+            // A bit that didn't latch
+            read(0xC4, 0x41),
+            write_bytes(&[0xFE, 1, 2, 3]),
+            ram_burst_read(&[1, 2, 3]),
+            write_bytes(&[0xFE, 4, 5, 6]),
+            ram_burst_read(&[4, 0xFF, 6]),
+        ]);
+        rtc.write_ram_verified(2, 0x42).unwrap();
+        assert!(matches!(
+            rtc.write_ram_verified(2, 0x43),
+            Err(Ds1302Error::Verify)
+        ));
+        assert_eq!(rtc.write_ram_burst_verified(&[1, 2, 3]).unwrap(), 3);
+        assert!(matches!(
+            rtc.write_ram_burst_verified(&[4, 5, 6]),
+            Err(Ds1302Error::Verify)
+        ));
+        spi.done();
+    }
+
+    #[test]
+    fn iso8601_output_of_several_datetimes() {
+        let cases = [
+            (Hours::Hour24(0), 0, 0, 6, 1, 1, 2000, "2000-01-01T00:00:00"),
+            (
+                Hours::Hour12pm(7),
+                24,
+                5,
+                5,
+                19,
+                11,
+                2021,
+                "2021-11-19T19:24:05",
+            ),
+            (
+                Hours::Hour12am(12),
+                30,
+                0,
+                4,
+                29,
+                2,
+                2024,
+                "2024-02-29T00:30:00",
+            ),
+            (
+                Hours::Hour12pm(12),
+                0,
+                59,
+                3,
+                31,
+                12,
+                2099,
+                "2099-12-31T12:00:59",
+            ),
+        ];
+        for (hours, minutes, seconds, day, date, month, year, expected) in cases {
+            let datetime = DateTime {
+                clock: Clock::new(hours, minutes, seconds).unwrap(),
+                calendar: Calendar::new(day, date, month, year).unwrap(),
+            };
+            let mut buf = [0_u8; 19];
+            assert_eq!(datetime.write_iso8601(&mut buf).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn iso8601_rejects_fields_out_of_range() {
+        let valid = DateTime {
+            clock: Clock::new(Hours::Hour24(12), 0, 0).unwrap(),
+            calendar: Calendar::new(1, 1, 1, 2021).unwrap(),
+        };
+        let mut minutes = valid;
+        minutes.clock.minutes = 60;
+        let mut month = valid;
+        month.calendar.month = 13;
+        let mut date = valid;
+        date.calendar.month = 2;
+        date.calendar.date = 29;
+        let mut hours = valid;
+        hours.clock.hours = Hours::Hour12pm(13);
+        for datetime in [minutes, month, date, hours] {
+            let mut buf = [0_u8; 19];
+            let result = datetime.write_iso8601(&mut buf);
+            assert!(matches!(result, Err(Ds1302Error::Parameter)));
+        }
+    }
+
+    #[test]
+    fn write_protect_raw_is_a_single_read() {
+        let (mut rtc, mut spi) = rtc(&[read(0x8E, 0x80), read(0x8E, 0x00)]);
+        assert_eq!(rtc.write_protect_raw().unwrap(), 0x80);
+        assert_eq!(rtc.write_protect_raw().unwrap(), 0x00);
+        spi.done();
+    }
+
+    fn datetime(
+        year: u16,
+        month: u8,
+        date: u8,
+        hours: Hours,
+        minutes: u8,
+        seconds: u8,
+    ) -> DateTime {
+        DateTime {
+            clock: Clock::new(hours, minutes, seconds).unwrap(),
+            calendar: calendar(year, month, date),
+        }
+    }
+
+    #[test]
+    fn add_seconds_crosses_month_leap_day_and_year_boundaries() {
+        let mut moved = datetime(2021, 4, 30, Hours::Hour24(23), 59, 50);
+        moved.add_seconds(15).unwrap();
+        assert_eq!(moved, datetime(2021, 5, 1, Hours::Hour24(0), 0, 5));
+
+        let mut moved = datetime(2024, 2, 28, Hours::Hour24(23), 0, 0);
+        moved.add_seconds(3_600).unwrap();
+        assert_eq!(moved, datetime(2024, 2, 29, Hours::Hour24(0), 0, 0));
+        moved.add_seconds(86_400).unwrap();
+        assert_eq!(moved, datetime(2024, 3, 1, Hours::Hour24(0), 0, 0));
+
+        let mut moved = datetime(2023, 2, 28, Hours::Hour24(12), 0, 0);
+        moved.add_seconds(86_400).unwrap();
+        assert_eq!(moved, datetime(2023, 3, 1, Hours::Hour24(12), 0, 0));
+
+        let mut moved = datetime(2021, 12, 31, Hours::Hour24(23), 59, 59);
+        moved.add_seconds(1).unwrap();
+        assert_eq!(moved, datetime(2022, 1, 1, Hours::Hour24(0), 0, 0));
+    }
+
+    #[test]
+    fn add_seconds_keeps_the_hour_format_and_clamps_at_2099() {
+        let mut moved = datetime(2021, 11, 19, Hours::Hour12am(11), 59, 59);
+        moved.add_seconds(1).unwrap();
+        assert!(matches!(moved.clock.hours, Hours::Hour12pm(12)));
+
+        let mut moved = datetime(2099, 12, 31, Hours::Hour24(23), 0, 0);
+        moved.add_seconds(u32::MAX).unwrap();
+        assert_eq!(moved, datetime(2099, 12, 31, Hours::Hour24(23), 59, 59));
+
+        let mut invalid = datetime(2021, 1, 1, Hours::Hour24(0), 0, 0);
+        invalid.calendar.month = 13;
+        assert!(matches!(
+            invalid.add_seconds(1),
+            Err(Ds1302Error::Parameter)
+        ));
+        assert_eq!(invalid.calendar.month, 13);
+    }
+
+    #[test]
+    fn advance_writes_the_moved_datetime_back() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        rtc.set_datetime(datetime(2024, 2, 28, Hours::Hour24(23), 59, 30))
+            .unwrap();
+        rtc.advance(45).unwrap();
+        assert_eq!(
+            rtc.get_datetime().unwrap(),
+            datetime(2024, 2, 29, Hours::Hour24(0), 0, 15)
+        );
+    }
+
+    #[test]
+    fn set_clock_calendar_rejects_invalid_fields_without_traffic() {
+        let (mut rtc, mut spi) = rtc(&[]);
+        let clock = Clock::new(Hours::Hour24(12), 0, 0).unwrap();
+        let calendar = Calendar::new(1, 1, 1, 2021).unwrap();
+        let invalid_calendars = [
+            Calendar {
+                month: 0,
+                ..calendar
+            },
+            Calendar {
+                month: 13,
+                ..calendar
+            },
+            Calendar {
+                date: 0,
+                ..calendar
+            },
+            Calendar {
+                date: 31,
+                month: 4,
+                ..calendar
+            },
+            Calendar {
+                date: 29,
+                month: 2,
+                ..calendar
+            },
+            Calendar { day: 8, ..calendar },
+        ];
+        for invalid in invalid_calendars {
+            assert!(rejected(rtc.set_clock_calendar(clock, invalid)));
+        }
+        let invalid_clocks = [
+            Clock {
+                seconds: 60,
+                ..clock
+            },
+            Clock {
+                minutes: 60,
+                ..clock
+            },
+            Clock {
+                hours: Hours::Hour24(24),
+                ..clock
+            },
+            Clock {
+                hours: Hours::Hour12am(0),
+                ..clock
+            },
+        ];
+        for invalid in invalid_clocks {
+            assert!(rejected(rtc.set_clock_calendar(invalid, calendar)));
+        }
+        spi.done();
+    }
+
+    #[test]
+    fn wait_for_second_change_returns_the_new_seconds() {
+        let (mut rtc, mut spi) = rtc(&[
+            read(0x80, 0x58),
+            read(0x80, 0x58),
+            read(0x80, 0x58),
+            read(0x80, 0x59),
+            // Halted while waiting
+            read(0x80, 0x10),
+            read(0x80, 0x90),
+        ]);
+        assert_eq!(rtc.wait_for_second_change().unwrap(), 59);
+        assert!(matches!(
+            rtc.wait_for_second_change(),
+            Err(Ds1302Error::Unknown)
+        ));
+        spi.done();
+    }
+
+    #[test]
+    fn wait_for_second_change_gives_up_on_a_stuck_clock() {
+        // The fake chip has no oscillator, its seconds never change
+        let memory = memory();
+        let result = fake_rtc(&memory).wait_for_second_change();
+        assert!(matches!(result, Err(Ds1302Error::Unknown)));
+    }
+
+    #[test]
+    fn set_day_accepts_1_through_7() {
+        let (mut rtc, mut spi) = rtc(&[read(0x8E, 0x00), write(0x8A, 0x01), write(0x8A, 0x07)]);
+        rtc.set_day(1).unwrap();
+        rtc.set_day(7).unwrap();
+        assert!(rejected(rtc.set_day(0)));
+        assert!(rejected(rtc.set_day(8)));
+        spi.done();
+    }
+
+    #[test]
+    fn raw_clock_calendar_restores_a_register_dump() {
+        let source = memory();
+        // Halted at 7:24:56 PM in 12-hour format, Friday 2021-11-19
+        let regs = [0xD6, 0x24, 0xA7, 0x19, 0x11, 0x05, 0x21];
+        let mut rtc = fake_rtc(&source);
+        rtc.set_clock_calendar_raw(&regs).unwrap();
+        let dump = rtc.dump_registers().unwrap();
+        assert_eq!(dump[..7], regs);
+
+        let target = memory();
+        let mut restored = fake_rtc(&target);
+        let mut bytes = [0_u8; 7];
+        bytes.copy_from_slice(&dump[..7]);
+        restored.set_clock_calendar_raw(&bytes).unwrap();
+        assert_eq!(restored.dump_registers().unwrap(), dump);
+        assert!(!restored.is_running().unwrap());
+    }
+
+    #[test]
+    fn mode_is_a_copyable_hashable_key() {
+        use std::collections::HashMap;
+
+        let mut labels = HashMap::new();
+        labels.insert(Mode::Hour12, "12h");
+        labels.insert(Mode::Hour24, "24h");
+        let rtc = fake_rtc(&memory());
+        let mode = rtc.mode();
+        assert_eq!(labels[&mode], "24h");
+        // Copy: the driver still has its mode
+        assert_eq!(rtc.mode(), mode);
+        assert_ne!(Mode::Hour12, Mode::Hour24);
+    }
+
+    #[test]
+    fn stored_mode_updates_after_set_clock_mode() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        assert_eq!(rtc.mode(), Mode::Hour24);
+        rtc.set_clock_mode(Mode::Hour12).unwrap();
+        assert_eq!(rtc.mode(), Mode::Hour12);
+        // Midnight in 12-hour format
+        assert_eq!(memory.borrow()[2], 0x92);
+        rtc.set_clock_mode(Mode::Hour24).unwrap();
+        assert_eq!(rtc.mode(), Mode::Hour24);
+        assert_eq!(memory.borrow()[2], 0x00);
+    }
+
+    #[test]
+    fn tc_enable_checked_writes_only_safe_configurations() {
+        let (mut rtc, mut spi) = rtc(&[read(0x8E, 0x00), write(0x90, 0xA5), write(0x90, 0xAB)]);
+        // 2.15 mA at 5 V
+        rtc.tc_enable_checked(Ds::ONE07V, Rs::R2K, 5_000).unwrap();
+        // 0.24 mA at 3.3 V
+        rtc.tc_enable_checked(Ds::TWO14V, Rs::R8K, 3_300).unwrap();
+        // 2.4 mA at 5.5 V
+        assert!(rejected(rtc.tc_enable_checked(Ds::ONE07V, Rs::R2K, 5_500)));
+        // Nothing left above the drop of two diodes
+        assert!(rejected(rtc.tc_enable_checked(Ds::TWO14V, Rs::R8K, 1_400)));
+        spi.done();
+    }
+
+    #[test]
+    fn seconds_until_and_is_past_mix_hour_formats() {
+        // 2021-05-19 13:00:00, a Wednesday, in 24-hour and in 12-hour format
+        let now24 = [0x00, 0x00, 0x13, 0x19, 0x05, 0x03, 0x21];
+        let now12 = [0x00, 0x00, 0xA1, 0x19, 0x05, 0x03, 0x21];
+        let (mut rtc, mut spi) = rtc(&[
+            burst_read(&now24),
+            burst_read(&now12),
+            burst_read(&now24),
+            burst_read(&now12),
+            burst_read(&now24),
+            burst_read(&now12),
+        ]);
+        let before = datetime(2021, 5, 19, Hours::Hour12pm(12), 59, 0);
+        let exact = datetime(2021, 5, 19, Hours::Hour24(13), 0, 0);
+        let after = datetime(2021, 5, 20, Hours::Hour12am(1), 0, 30);
+        assert_eq!(rtc.seconds_until(&after).unwrap(), 12 * 3600 + 30);
+        assert_eq!(rtc.seconds_until(&before).unwrap(), -60);
+        assert!(!rtc.is_past(&after).unwrap());
+        assert!(rtc.is_past(&before).unwrap());
+        assert!(rtc.is_past(&exact).unwrap());
+        assert_eq!(rtc.seconds_until(&exact).unwrap(), 0);
+        spi.done();
+    }
+
+    #[test]
+    fn seconds_until_rejects_invalid_dates() {
+        let (mut rtc, mut spi) = rtc(&[burst_read(&[0x00, 0x60, 0x12, 0x19, 0x05, 0x03, 0x21])]);
+        let mut target = datetime(2021, 5, 19, Hours::Hour24(13), 0, 0);
+        target.calendar.date = 32;
+        // Nothing is read for an invalid target
+        assert!(rejected(rtc.seconds_until(&target)));
+        target.calendar.date = 19;
+        assert!(matches!(
+            rtc.is_past(&target),
+            Err(Ds1302Error::InvalidData)
+        ));
+        spi.done();
+    }
+
+    #[test]
+    fn clear_ram_fills_every_byte() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        rtc.write_ram_all(&core::array::from_fn(|i| i as u8))
+            .unwrap();
+        rtc.clear_ram(0x5A).unwrap();
+        assert_eq!(rtc.read_ram_all().unwrap(), [0x5A; RAM_SIZE]);
+        rtc.zero_ram().unwrap();
+        assert_eq!(rtc.read_ram_all().unwrap(), [0; RAM_SIZE]);
+        assert!(memory.borrow()[32..63].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn packed_bcd_round_trips_in_both_hour_formats() {
+        let pm = Clock::new(Hours::Hour12pm(1), 0, 0).unwrap();
+        assert_eq!(pm.packed_bcd(), 0x00_A1_00_00);
+        let clock = Clock::new(Hours::Hour24(12), 34, 56).unwrap();
+        assert_eq!(clock.packed_bcd(), 0x00_12_34_56);
+        for hours in 0..24 {
+            for (minutes, seconds) in [(0, 0), (34, 56), (59, 59)] {
+                let clock = Clock::new(Hours::Hour24(hours), minutes, seconds).unwrap();
+                for clock in [
+                    clock,
+                    Clock::new(clock.hours.convert(), minutes, seconds).unwrap(),
+                ] {
+                    let unpacked = Clock::from_packed_bcd(clock.packed_bcd()).unwrap();
+                    assert_eq!(unpacked, clock);
+                    // The hour format is kept
+                    assert_eq!(unpacked.packed_bcd(), clock.packed_bcd());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn from_packed_bcd_rejects_invalid_layouts() {
+        for packed in [
+            0x01_12_00_00,
+            0x00_24_00_00,
+            0x00_1A_00_00,
+            0x00_12_60_00,
+            0x00_12_00_5F,
+            0x00_80_00_00,
+        ] {
+            assert!(matches!(
+                Clock::from_packed_bcd(packed),
+                Err(Ds1302Error::Parameter)
+            ));
+        }
+    }
+
+    #[test]
+    fn bcd_round_trips_every_decimal() {
+        for n in 0..=99_u8 {
+            assert_eq!(bcd_to_decimal(decimal_to_bcd(n)), n);
+            let bcd = decimal_to_bcd_checked(n).unwrap();
+            assert_eq!(bcd_to_decimal_checked(bcd), Some(n), "{n}");
+        }
+    }
+
+    #[test]
+    fn checked_bcd_rejects_every_nibble_above_9() {
+        let invalid = (0..=255_u8).filter(|byte| byte >> 4 > 9 || byte & 0x0F > 9);
+        assert_eq!(invalid.clone().count(), 256 - 100);
+        for byte in invalid {
+            assert_eq!(bcd_to_decimal_checked(byte), None, "{byte:#04x}");
+        }
+    }
+
+    #[test]
+    fn burst_readers_agree_on_the_same_registers() {
+        let regs = [0x80 | 0x56, 0x34, 0xA1, 0x29, 0x02, 0x04, 0x24];
+        let (mut rtc, mut spi) =
+            rtc(&[burst_read(&regs[..3]), burst_read(&regs), burst_read(&regs)]);
+        let clock = rtc.get_clock().unwrap();
+        let calendar = rtc.get_calendar().unwrap();
+        let (both_clock, both_calendar) = rtc.get_clock_calendar().unwrap();
+        assert!(matches!(clock.hours, Hours::Hour12pm(1)));
+        assert_eq!((clock.minutes, clock.seconds), (34, 56));
+        assert_eq!(calendar, Calendar::new(4, 29, 2, 2024).unwrap());
+        assert_eq!(
+            (clock.packed_bcd(), calendar),
+            (both_clock.packed_bcd(), both_calendar)
+        );
+        spi.done();
+    }
+
+    #[test]
+    fn advance_rejects_a_non_bcd_register_without_writing() {
+        // Month 0x0A would decode to 10
+        let (mut rtc, mut spi) = rtc(&[burst_read(&[0x00, 0x00, 0x12, 0x19, 0x0A, 0x03, 0x21])]);
+        assert!(matches!(rtc.advance(60), Err(Ds1302Error::InvalidData)));
+        spi.done();
+    }
+
+    #[test]
+    fn out_of_range_hours_compare_without_overflow() {
+        let hours = Hours::Hour12pm(250);
+        assert_ne!(hours, Hours::Hour24(12));
+        assert_eq!(hours.cmp(&Hours::Hour12pm(255)), Ordering::Greater);
+        assert_eq!(u8::from(&Hours::Hour12pm(255)), 15);
+        let clock = Clock {
+            hours: Hours::Hour12pm(255),
+            minutes: 0,
+            seconds: 0,
+        };
+        assert_eq!(clock.seconds_since_midnight(), 15 * 3600);
+    }
+
+    #[test]
+    fn burst_getters_reject_registers_that_are_not_bcd() {
+        // Seconds 0x1A would decode to 20
+        let seconds = [0x1A, 0x34, 0x12, 0x19, 0x05, 0x03, 0x21];
+        // Date 0x3F would decode to 45
+        let date = [0x56, 0x34, 0x12, 0x3F, 0x05, 0x03, 0x21];
+        let (mut rtc, mut spi) = rtc(&[
+            burst_read(&seconds[..3]),
+            burst_read(&seconds),
+            burst_read(&date),
+            burst_read(&date),
+            burst_read(&date[..3]),
+        ]);
+        assert!(matches!(rtc.get_clock(), Err(Ds1302Error::InvalidData)));
+        assert!(matches!(
+            rtc.get_clock_calendar(),
+            Err(Ds1302Error::InvalidData)
+        ));
+        assert!(matches!(rtc.get_calendar(), Err(Ds1302Error::InvalidData)));
+        assert!(matches!(rtc.get_datetime(), Err(Ds1302Error::InvalidData)));
+        // The calendar isn't read by get_clock
+        assert_eq!(rtc.get_clock().unwrap().seconds, 56);
+        spi.done();
+    }
+
+    #[test]
+    fn raw_register_access_rejects_the_burst_commands() {
+        let (mut rtc, mut spi) = rtc(&[]);
+        for addr in [0xBE, 0xBF, 0xFE, 0xFF] {
+            assert!(rejected(rtc.read_register(addr)));
+            assert!(rejected(rtc.write_register(addr, 0)));
+        }
+        spi.done();
+    }
+}
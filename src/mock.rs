@@ -0,0 +1,323 @@
+//! Test doubles shared by the unit tests
+
+use crate::{BitOrder, Delay, DriverError, Ds1302Error, Interface, Mode, SpiNoCsInterface, DS1302};
+use core::cell::RefCell;
+use core::convert::Infallible;
+use embedded_hal_mock::eh0::spi::{Mock, Transaction};
+use std::rc::Rc;
+use std::vec;
+use std::vec::Vec;
+
+pub(crate) const TIMER_HZ: u32 = 1_000_000;
+
+/// Timer whose countdowns are always over
+pub(crate) struct NoWait;
+
+impl Delay<TIMER_HZ> for NoWait {
+    type Error = Infallible;
+
+    fn now(&mut self) -> fugit::TimerInstantU32<TIMER_HZ> {
+        fugit::TimerInstantU32::from_ticks(0)
+    }
+
+    fn start(&mut self, _duration: fugit::TimerDurationU32<TIMER_HZ>) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Timer recording the duration of every countdown, in ticks
+#[derive(Default)]
+pub(crate) struct Recorder {
+    pub(crate) started: Vec<u32>,
+}
+
+impl Delay<TIMER_HZ> for Recorder {
+    type Error = Infallible;
+
+    fn now(&mut self) -> fugit::TimerInstantU32<TIMER_HZ> {
+        fugit::TimerInstantU32::from_ticks(0)
+    }
+
+    fn start(&mut self, duration: fugit::TimerDurationU32<TIMER_HZ>) -> Result<(), Infallible> {
+        self.started.push(duration.ticks());
+        Ok(())
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Error of [`FailingDelay`]
+#[derive(Debug, PartialEq)]
+pub(crate) struct TimerFault;
+
+/// Timer whose countdowns always fail
+pub(crate) struct FailingDelay;
+
+impl Delay<TIMER_HZ> for FailingDelay {
+    type Error = TimerFault;
+
+    fn now(&mut self) -> fugit::TimerInstantU32<TIMER_HZ> {
+        fugit::TimerInstantU32::from_ticks(0)
+    }
+
+    fn start(&mut self, _duration: fugit::TimerDurationU32<TIMER_HZ>) -> Result<(), TimerFault> {
+        Err(TimerFault)
+    }
+
+    fn wait(&mut self) -> nb::Result<(), TimerFault> {
+        Err(nb::Error::Other(TimerFault))
+    }
+}
+
+pub(crate) type Rtc = DS1302<SpiNoCsInterface<Mock>, NoWait, TIMER_HZ>;
+pub(crate) type Error = DriverError<SpiNoCsInterface<Mock>, NoWait, TIMER_HZ>;
+
+/// Single register read of the write address `addr`, answering `value`
+pub(crate) fn read(addr: u8, value: u8) -> Transaction {
+    Transaction::transfer(vec![addr | 1, 0], vec![0, value])
+}
+
+/// Single register write of `value` at `addr`
+pub(crate) fn write(addr: u8, value: u8) -> Transaction {
+    Transaction::write(vec![addr, value])
+}
+
+/// Clock burst read answering `regs`, starting at the seconds register
+pub(crate) fn burst_read(regs: &[u8]) -> Transaction {
+    let mut response = vec![0];
+    response.extend_from_slice(regs);
+    let mut expected = vec![0_u8; response.len()];
+    expected[0] = 0xBF;
+    Transaction::transfer(expected, response)
+}
+
+/// Write of `bytes`, starting with the command byte
+pub(crate) fn write_bytes(bytes: &[u8]) -> Transaction {
+    Transaction::write(bytes.to_vec())
+}
+
+/// RAM burst read answering `ram`, starting at index 0
+pub(crate) fn ram_burst_read(ram: &[u8]) -> Transaction {
+    let mut response = vec![0];
+    response.extend_from_slice(ram);
+    let mut expected = vec![0_u8; response.len()];
+    expected[0] = 0xFF;
+    Transaction::transfer(expected, response)
+}
+
+/// Driver created by `new_no_cs` on a running clock in 24-hour format, then expecting `spi`.
+/// The returned mock shares the expectations of the driver's, call `done` on it at the end.
+pub(crate) fn rtc(spi: &[Transaction]) -> (Rtc, Mock) {
+    let mut expected: Vec<Transaction> = vec![read(0x80, 0x00), read(0x84, 0x12)];
+    expected.extend_from_slice(spi);
+    let mock = Mock::new(&expected);
+    let rtc = DS1302::new_no_cs(mock.clone(), Mode::Hour24, NoWait).unwrap();
+    (rtc, mock)
+}
+
+/// Error of [`FailingSpi`]
+#[derive(Debug, PartialEq)]
+pub(crate) struct BusFault;
+
+/// SPI bus failing every transaction
+pub(crate) struct FailingSpi;
+
+impl embedded_hal::blocking::spi::Transfer<u8> for FailingSpi {
+    type Error = BusFault;
+
+    fn transfer<'w>(&mut self, _words: &'w mut [u8]) -> Result<&'w [u8], BusFault> {
+        Err(BusFault)
+    }
+}
+
+impl embedded_hal::blocking::spi::Write<u8> for FailingSpi {
+    type Error = BusFault;
+
+    fn write(&mut self, _words: &[u8]) -> Result<(), BusFault> {
+        Err(BusFault)
+    }
+}
+
+/// Error of [`Pin`]
+#[derive(Debug, PartialEq)]
+pub(crate) struct PinFault;
+
+/// Chip select pin recording its level, failing every change if `fails` is set
+#[derive(Default)]
+pub(crate) struct Pin {
+    pub(crate) fails: bool,
+    pub(crate) high: bool,
+}
+
+impl embedded_hal::digital::v2::OutputPin for Pin {
+    type Error = PinFault;
+
+    fn set_low(&mut self) -> Result<(), PinFault> {
+        self.set(false)
+    }
+
+    fn set_high(&mut self) -> Result<(), PinFault> {
+        self.set(true)
+    }
+}
+
+impl Pin {
+    fn set(&mut self, high: bool) -> Result<(), PinFault> {
+        if self.fails {
+            return Err(PinFault);
+        }
+        self.high = high;
+        Ok(())
+    }
+}
+
+/// Registers and RAM of a [`FakeChip`], shared so that they outlive a driver like on a
+/// battery-backed chip. Index `n` holds the register of the command `0x80 + 2 * n`:
+/// 0..=8 the clock, write protect and trickle charger registers, 32..=62 the RAM.
+pub(crate) type Memory = Rc<RefCell<[u8; 64]>>;
+
+/// Zeroed memory of a [`FakeChip`]
+pub(crate) fn memory() -> Memory {
+    Rc::new(RefCell::new([0; 64]))
+}
+
+/// Interface emulating the registers and RAM of the chip, without the oscillator
+pub(crate) struct FakeChip {
+    pub(crate) memory: Memory,
+}
+
+impl FakeChip {
+    /// Chip holding `memory`, zeroed memory is a running clock at midnight in 24-hour format
+    pub(crate) fn new(memory: &Memory) -> Self {
+        FakeChip {
+            memory: memory.clone(),
+        }
+    }
+
+    // First memory index and length of the command byte `command`, at most `len` bytes
+    fn span(command: u8, len: usize) -> (usize, usize) {
+        let (start, size) = match command | 1 {
+            0xBF => (0, 8),
+            0xFF => (32, 31),
+            _ => (usize::from((command & 0x7E) >> 1), 1),
+        };
+        (start, size.min(len))
+    }
+}
+
+impl Interface for FakeChip {
+    type SpiError = Infallible;
+    type PinError = Infallible;
+
+    fn transfer(&mut self, bytes: &mut [u8]) -> Result<(), Ds1302Error<Infallible, Infallible>> {
+        let (start, len) = FakeChip::span(bytes[0], bytes.len() - 1);
+        let memory = self.memory.borrow();
+        bytes[1..=len].copy_from_slice(&memory[start..start + len]);
+        Ok(())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Ds1302Error<Infallible, Infallible>> {
+        let (start, len) = FakeChip::span(bytes[0], bytes.len() - 1);
+        let mut memory = self.memory.borrow_mut();
+        // Only the write protect register can be written while the protection is set
+        if memory[7] & 0x80 != 0 && start != 7 {
+            return Ok(());
+        }
+        memory[start..start + len].copy_from_slice(&bytes[1..=len]);
+        Ok(())
+    }
+}
+
+/// Driver on a [`FakeChip`] holding `memory`
+pub(crate) fn fake_rtc(memory: &Memory) -> DS1302<FakeChip, NoWait, TIMER_HZ> {
+    DS1302::with_interface(
+        FakeChip::new(memory),
+        Mode::Hour24,
+        NoWait,
+        BitOrder::LsbFirst,
+    )
+    .unwrap()
+}
+
+/// Test doubles of the async driver, on an `embedded-hal` 1.0 `SpiDevice`
+#[cfg(feature = "async")]
+pub(crate) mod asynch {
+    use crate::asynch::DS1302;
+    use crate::Mode;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll};
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+    use embedded_hal_mock::eh1::spi::{Mock, Transaction};
+    use std::task::Waker;
+    use std::vec;
+    use std::vec::Vec;
+
+    pub(crate) type Rtc = DS1302<Mock<u8>, NoopDelay>;
+
+    /// Run `future` to completion, the mocks never make it wait
+    pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    /// Single register read of the write address `addr`, answering `value`
+    pub(crate) fn read(addr: u8, value: u8) -> Vec<Transaction<u8>> {
+        vec![
+            Transaction::transaction_start(),
+            Transaction::transfer_in_place(vec![addr | 1, 0], vec![0, value]),
+            Transaction::transaction_end(),
+        ]
+    }
+
+    /// Single register write of `value` at `addr`
+    pub(crate) fn write(addr: u8, value: u8) -> Vec<Transaction<u8>> {
+        write_bytes(&[addr, value])
+    }
+
+    /// Write of `bytes`, starting with the command byte
+    pub(crate) fn write_bytes(bytes: &[u8]) -> Vec<Transaction<u8>> {
+        vec![
+            Transaction::transaction_start(),
+            Transaction::write_vec(bytes.to_vec()),
+            Transaction::transaction_end(),
+        ]
+    }
+
+    /// Clock burst read answering `regs`, starting at the seconds register
+    pub(crate) fn burst_read(regs: &[u8]) -> Vec<Transaction<u8>> {
+        let mut response = vec![0];
+        response.extend_from_slice(regs);
+        let mut expected = vec![0_u8; response.len()];
+        expected[0] = 0xBF;
+        vec![
+            Transaction::transaction_start(),
+            Transaction::transfer_in_place(expected, response),
+            Transaction::transaction_end(),
+        ]
+    }
+
+    /// Driver created by `new` on a running clock in 24-hour format, then expecting `spi`.
+    /// The returned mock shares the expectations of the driver's, call `done` on it at the end.
+    pub(crate) fn rtc(spi: &[Vec<Transaction<u8>>]) -> (Rtc, Mock<u8>) {
+        let mut expected = read(0x80, 0x00);
+        expected.extend(read(0x84, 0x12));
+        for transactions in spi {
+            expected.extend_from_slice(transactions);
+        }
+        let mock = Mock::new(&expected);
+        let rtc = block_on(DS1302::new(mock.clone(), Mode::Hour24, NoopDelay::new())).unwrap();
+        (rtc, mock)
+    }
+}
@@ -0,0 +1,30 @@
+//! [`Delay`] that never waits, enabled by the `no-delay` feature
+
+use crate::Delay;
+use core::convert::Infallible;
+
+/// [`Delay`] implementation that doesn't wait at all, `DS1302::new(spi, cs, mode, NoDelay)`
+///
+/// Only use it when the bus already guarantees the 4 µs CE inactive time between two
+/// transactions, e.g. with a slow SPI clock or when the code between two driver calls is slow
+/// enough. Otherwise the chip may miss the start of the next command.
+/// `now` always returns 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NoDelay;
+
+impl<const TIMER_HZ: u32> Delay<TIMER_HZ> for NoDelay {
+    type Error = Infallible;
+
+    fn now(&mut self) -> fugit::TimerInstantU32<TIMER_HZ> {
+        fugit::TimerInstantU32::from_ticks(0)
+    }
+
+    fn start(&mut self, _duration: fugit::TimerDurationU32<TIMER_HZ>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        Ok(())
+    }
+}
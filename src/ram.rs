@@ -0,0 +1,183 @@
+//! Helpers storing data in the battery-backed RAM
+
+use crate::{Delay, DriverError, Ds1302Error, Interface, DS1302, RAM_SIZE};
+use core::ops::Range;
+
+/// `u32` counter kept in 4 bytes of the RAM, little-endian, e.g. a boot counter
+///
+/// The counter only stores its offset, the value lives in the chip and survives power cycles
+/// as long as the backup supply does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RamCounter {
+    offset: u8,
+}
+
+impl RamCounter {
+    /// Counter in the RAM bytes `offset..offset + 4`,
+    /// fails with `Ds1302Error::Parameter` if they don't fit into the 31 bytes
+    pub fn new(offset: u8) -> Result<Self, Ds1302Error> {
+        if usize::from(offset) + 4 > RAM_SIZE {
+            return Err(Ds1302Error::Parameter);
+        }
+        Ok(RamCounter { offset })
+    }
+
+    /// Index of the first RAM byte of the counter
+    pub fn offset(&self) -> u8 {
+        self.offset
+    }
+
+    /// Read the counter
+    pub fn load<IF, CLK, const TIMER_HZ: u32>(
+        &self,
+        rtc: &mut DS1302<IF, CLK, TIMER_HZ>,
+    ) -> Result<u32, DriverError<IF, CLK, TIMER_HZ>>
+    where
+        IF: Interface,
+        CLK: Delay<TIMER_HZ>,
+    {
+        let mut bytes = [0_u8; 4];
+        rtc.read_ram_range(self.offset, &mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    /// Write `value` to the counter
+    pub fn store<IF, CLK, const TIMER_HZ: u32>(
+        &self,
+        rtc: &mut DS1302<IF, CLK, TIMER_HZ>,
+        value: u32,
+    ) -> Result<(), DriverError<IF, CLK, TIMER_HZ>>
+    where
+        IF: Interface,
+        CLK: Delay<TIMER_HZ>,
+    {
+        rtc.write_ram_range(self.offset, &value.to_le_bytes())
+    }
+
+    /// Add one to the counter and return the new value, `u32::MAX` wraps around to 0
+    pub fn increment<IF, CLK, const TIMER_HZ: u32>(
+        &self,
+        rtc: &mut DS1302<IF, CLK, TIMER_HZ>,
+    ) -> Result<u32, DriverError<IF, CLK, TIMER_HZ>>
+    where
+        IF: Interface,
+        CLK: Delay<TIMER_HZ>,
+    {
+        let value = self.load(rtc)?.wrapping_add(1);
+        self.store(rtc, value)?;
+        Ok(value)
+    }
+}
+
+/// Copy of the RAM changed by [`DS1302::ram_transaction`]
+///
+/// The RAM burst always starts at index 0, so the transaction writes the bytes up to the
+/// highest changed index in one burst.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RamBuffer {
+    data: [u8; RAM_SIZE],
+    // Number of bytes to write back, the highest changed index + 1
+    dirty_len: usize,
+}
+
+impl RamBuffer {
+    pub(crate) fn new(data: [u8; RAM_SIZE]) -> Self {
+        RamBuffer { data, dirty_len: 0 }
+    }
+
+    /// Byte at `index`, fails with `Ds1302Error::Parameter` for an index above 30
+    pub fn get(&self, index: u8) -> Result<u8, Ds1302Error> {
+        self.data
+            .get(usize::from(index))
+            .copied()
+            .ok_or(Ds1302Error::Parameter)
+    }
+
+    /// Change the byte at `index`, fails with `Ds1302Error::Parameter` for an index above 30
+    pub fn set(&mut self, index: u8, value: u8) -> Result<(), Ds1302Error> {
+        let byte = self
+            .data
+            .get_mut(usize::from(index))
+            .ok_or(Ds1302Error::Parameter)?;
+        *byte = value;
+        self.dirty_len = self.dirty_len.max(usize::from(index) + 1);
+        Ok(())
+    }
+
+    /// The whole RAM with the changes made so far
+    pub fn as_bytes(&self) -> &[u8; RAM_SIZE] {
+        &self.data
+    }
+
+    // Bytes to write back, empty if nothing was changed
+    pub(crate) fn dirty(&self) -> &[u8] {
+        &self.data[..self.dirty_len]
+    }
+}
+
+/// CRC-8/MAXIM (polynomial 0x31, reflected, initial value 0) of `data`, the 1-Wire CRC of
+/// Maxim/Dallas chips
+///
+/// Used by [`DS1302::update_ram_crc`] and [`DS1302::verify_ram`] to detect RAM contents
+/// corrupted by a brownout.
+pub fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0_u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x01 != 0 {
+                (crc >> 1) ^ 0x8C
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+// The CRC of a region is stored in the byte after it, which must still be in the RAM
+pub(crate) fn crc_region_is_valid(region: &Range<u8>) -> bool {
+    region.start <= region.end && usize::from(region.end) < RAM_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{fake_rtc, memory};
+
+    #[test]
+    fn counter_survives_a_power_cycle() {
+        let memory = memory();
+        let counter = RamCounter::new(27).unwrap();
+
+        let mut rtc = fake_rtc(&memory);
+        counter.store(&mut rtc, 0x0102_03FF).unwrap();
+        assert_eq!(counter.increment(&mut rtc).unwrap(), 0x0102_0400);
+        drop(rtc);
+
+        // The RAM is kept by the backup supply, a new driver finds the counter
+        let mut rtc = fake_rtc(&memory);
+        assert_eq!(counter.load(&mut rtc).unwrap(), 0x0102_0400);
+        assert_eq!(counter.increment(&mut rtc).unwrap(), 0x0102_0401);
+        // Little-endian in the bytes 27..=30
+        assert_eq!(&memory.borrow()[32 + 27..], &[0x01, 0x04, 0x02, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn counter_wraps_around_and_must_fit_into_the_ram() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        let counter = RamCounter::new(0).unwrap();
+        counter.store(&mut rtc, u32::MAX).unwrap();
+        assert_eq!(counter.increment(&mut rtc).unwrap(), 0);
+        assert!(RamCounter::new(27).is_ok());
+        assert!(RamCounter::new(28).is_err());
+    }
+
+    #[test]
+    fn crc8_matches_the_dallas_maxim_check_value() {
+        assert_eq!(crc8(b"123456789"), 0xA1);
+        assert_eq!(crc8(&[]), 0);
+    }
+}
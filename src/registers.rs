@@ -1,6 +1,9 @@
-use core::convert::From;
+use crate::Ds1302Error;
+use core::convert::{From, TryFrom};
+use core::fmt;
 
 /// Register definitions
+#[allow(clippy::upper_case_acronyms)]
 pub enum Register {
     SECONDS = 0x80,
     MINUTES = 0x82,
@@ -22,10 +25,80 @@ impl Register {
     }
 }
 
+const READ_BIT: u8 = 0x01;
+// Register 31 in bits 5..=1
+const BURST_BITS: u8 = 0x3E;
+
+/// Command byte starting every transaction
+///
+/// Bit 7 is always set, bit 6 selects the RAM (1) or the clock (0), bits 5..=1 are the register
+/// and bit 0 selects a read (1) or a write (0). The datasheet lists the write address of every
+/// register, which is what the constructors take. Register 31 is the burst of the clock or the
+/// RAM: all registers are transferred in one transaction, starting at the first one. A single
+/// register is transferred as two bytes, the command and the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Command(u8);
+
+impl Command {
+    /// Read the register at the datasheet address `addr`
+    pub const fn read(addr: u8) -> Self {
+        Command(addr | READ_BIT)
+    }
+
+    /// Write the register at the datasheet address `addr`
+    pub const fn write(addr: u8) -> Self {
+        Command(addr & !READ_BIT)
+    }
+
+    /// Read the clock and calendar registers in one burst
+    pub const fn burst_read() -> Self {
+        Command::read(Register::CLKBURS as u8)
+    }
+
+    /// Write the clock, calendar and write protect registers in one burst
+    pub const fn burst_write() -> Self {
+        Command::write(Register::CLKBURS as u8)
+    }
+
+    /// Read the RAM in one burst
+    pub const fn ram_burst_read() -> Self {
+        Command::read(Register::RAMBURS as u8)
+    }
+
+    /// Write the RAM in one burst
+    pub const fn ram_burst_write() -> Self {
+        Command::write(Register::RAMBURS as u8)
+    }
+
+    /// Byte sent on the bus
+    pub const fn byte(self) -> u8 {
+        self.0
+    }
+
+    /// Whether this command reads from the chip
+    pub const fn is_read(self) -> bool {
+        self.0 & READ_BIT != 0
+    }
+
+    /// Whether this command is a burst of the clock or the RAM, i.e. register 31
+    pub const fn is_burst(self) -> bool {
+        self.0 & BURST_BITS == BURST_BITS
+    }
+}
+
+impl From<Command> for u8 {
+    fn from(command: Command) -> Self {
+        command.byte()
+    }
+}
+
 /// Programmable Trickle Charger.
 pub(crate) struct TrickleCharger(u8);
 
 /// Trickle charger resistor select.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Rs {
     R2K,
     R4K,
@@ -42,6 +115,15 @@ impl Rs {
         }
     }
 
+    /// Resistance in ohms
+    pub fn ohms(&self) -> u32 {
+        match self {
+            Rs::R2K => 2_000,
+            Rs::R4K => 4_000,
+            Rs::R8K => 8_000,
+        }
+    }
+
     pub(crate) fn value(&self) -> u8 {
         match self {
             Rs::R2K => 0x01,
@@ -51,7 +133,29 @@ impl Rs {
     }
 }
 
+impl TryFrom<u8> for Rs {
+    type Error = Ds1302Error;
+
+    /// Resistor select bits (1..0) of a trickle charger register value,
+    /// fails with `Ds1302Error::Parameter` when no resistor is selected
+    fn try_from(b: u8) -> Result<Self, Self::Error> {
+        Rs::judge(b).ok_or(Ds1302Error::Parameter)
+    }
+}
+
+impl fmt::Display for Rs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rs::R2K => f.write_str("2k"),
+            Rs::R4K => f.write_str("4k"),
+            Rs::R8K => f.write_str("8k"),
+        }
+    }
+}
+
 /// Trickle charger diode select. diode drop 0.7v or 1.4v.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Ds {
     ONE07V = 0x04,
     TWO14V = 0x08,
@@ -66,6 +170,14 @@ impl Ds {
         }
     }
 
+    /// Voltage drop in millivolts
+    pub fn drop_mv(&self) -> u16 {
+        match self {
+            Ds::ONE07V => 700,
+            Ds::TWO14V => 1_400,
+        }
+    }
+
     pub(crate) fn value(&self) -> u8 {
         match self {
             Ds::ONE07V => 0x04,
@@ -74,15 +186,30 @@ impl Ds {
     }
 }
 
+impl TryFrom<u8> for Ds {
+    type Error = Ds1302Error;
+
+    /// Diode select bits (3..2) of a trickle charger register value,
+    /// fails with `Ds1302Error::Parameter` for the patterns without a diode
+    fn try_from(b: u8) -> Result<Self, Self::Error> {
+        Ds::judge(b).ok_or(Ds1302Error::Parameter)
+    }
+}
+
+impl fmt::Display for Ds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ds::ONE07V => f.write_str("0.7V"),
+            Ds::TWO14V => f.write_str("1.4V"),
+        }
+    }
+}
+
 impl TrickleCharger {
     pub fn get(&self) -> (bool, Option<Ds>, Option<Rs>) {
         let rs = Rs::judge(self.0);
         let ds = Ds::judge(self.0);
-        let tcs = if rs.is_none() || ds.is_none() || (self.0 & 0xF0 != 0xA0) {
-            false
-        } else {
-            true
-        };
+        let tcs = rs.is_some() && ds.is_some() && (self.0 & 0xF0 == 0xA0);
 
         (tcs, ds, rs)
     }
@@ -98,6 +225,43 @@ impl TrickleCharger {
     pub fn enable(ds: Ds, rs: Rs) -> u8 {
         rs.value() | ds.value() | 0xA0
     }
+
+    pub fn describe(&self) -> &'static str {
+        match self.0 {
+            0xA5 => "enabled, one diode, 2k",
+            0xA6 => "enabled, one diode, 4k",
+            0xA7 => "enabled, one diode, 8k",
+            0xA9 => "enabled, two diodes, 2k",
+            0xAA => "enabled, two diodes, 4k",
+            0xAB => "enabled, two diodes, 8k",
+            0x5C => "disabled (power-on value)",
+            _ => "disabled",
+        }
+    }
+}
+
+/// Human readable meaning of a trickle charger register value, e.g. from
+/// [`DS1302::tc_raw`](crate::DS1302::tc_raw). The datasheet enables the charger for the six
+/// patterns `0xA5`..=`0xAB` with one diode and one resistor selected, everything else disables it.
+pub fn tc_describe(byte: u8) -> &'static str {
+    TrickleCharger::from(byte).describe()
+}
+
+/// Maximum charging current in microamps, (Vcc - diode drop) / R from the datasheet.
+/// E.g. one diode and 2k at 5 V: (5 V - 0.7 V) / 2 kΩ = 2150 µA.
+/// Returns 0 if `vcc_mv` doesn't exceed the diode drop.
+pub fn tc_max_current_ua(ds: Ds, rs: Rs, vcc_mv: u16) -> u32 {
+    let mv = u32::from(vcc_mv.saturating_sub(ds.drop_mv()));
+    mv * 1_000 / rs.ohms()
+}
+
+/// Largest trickle charger current accepted by `tc_enable_checked`, in microamps.
+/// The datasheet's own example, one diode and 2 kΩ at 5 V, charges at about 2.2 mA.
+pub const TC_MAX_SAFE_CURRENT_UA: u32 = 2_200;
+
+// The supply must exceed the diode drop and the current stay within TC_MAX_SAFE_CURRENT_UA
+pub(crate) fn tc_is_safe(ds: Ds, rs: Rs, vcc_mv: u16) -> bool {
+    vcc_mv > ds.drop_mv() && tc_max_current_ua(ds, rs, vcc_mv) <= TC_MAX_SAFE_CURRENT_UA
 }
 
 impl From<u8> for TrickleCharger {
@@ -105,3 +269,95 @@ impl From<u8> for TrickleCharger {
         TrickleCharger(b)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trickle_charger_current_of_the_datasheet_examples() {
+        // (5.0 V - 0.7 V) / 2 kΩ = 2.15 mA
+        assert_eq!(tc_max_current_ua(Ds::ONE07V, Rs::R2K, 5_000), 2_150);
+        // (5.0 V - 1.4 V) / 8 kΩ = 0.45 mA
+        assert_eq!(tc_max_current_ua(Ds::TWO14V, Rs::R8K, 5_000), 450);
+        assert_eq!(tc_max_current_ua(Ds::ONE07V, Rs::R4K, 3_300), 650);
+    }
+
+    #[test]
+    fn trickle_charger_current_is_zero_below_the_diode_drop() {
+        assert_eq!(tc_max_current_ua(Ds::TWO14V, Rs::R2K, 1_000), 0);
+        assert_eq!(tc_max_current_ua(Ds::ONE07V, Rs::R2K, 700), 0);
+    }
+
+    #[test]
+    fn ds_and_rs_from_the_enabled_register_values() {
+        for (byte, ds, rs) in [
+            (0xA5, Ds::ONE07V, Rs::R2K),
+            (0xA6, Ds::ONE07V, Rs::R4K),
+            (0xA7, Ds::ONE07V, Rs::R8K),
+            (0xA9, Ds::TWO14V, Rs::R2K),
+            (0xAA, Ds::TWO14V, Rs::R4K),
+            (0xAB, Ds::TWO14V, Rs::R8K),
+        ] {
+            assert_eq!(Ds::try_from(byte).unwrap(), ds);
+            assert_eq!(Rs::try_from(byte).unwrap(), rs);
+            assert_eq!(TrickleCharger::enable(ds, rs), byte);
+        }
+    }
+
+    #[test]
+    fn ds_and_rs_reject_the_patterns_without_diode_or_resistor() {
+        // No resistor
+        assert!(Rs::try_from(0xA4).is_err());
+        // Diode bits 00 and 11 select no diode
+        assert!(Ds::try_from(0xA1).is_err());
+        assert!(Ds::try_from(0xAD).is_err());
+        // Power-on value
+        assert!(Ds::try_from(0x5C).is_err());
+        assert!(Rs::try_from(0x5C).is_err());
+    }
+
+    #[test]
+    fn ds_and_rs_display() {
+        use std::string::ToString;
+        assert_eq!(Ds::ONE07V.to_string(), "0.7V");
+        assert_eq!(Ds::TWO14V.to_string(), "1.4V");
+        assert_eq!(Rs::R2K.to_string(), "2k");
+        assert_eq!(Rs::R8K.to_string(), "8k");
+    }
+
+    #[test]
+    fn command_bytes_of_single_registers_and_bursts() {
+        assert_eq!(Command::read(Register::SECONDS.addr()).byte(), 0x81);
+        assert_eq!(Command::write(Register::SECONDS.addr()).byte(), 0x80);
+        assert_eq!(Command::read(Register::TCS.addr()).byte(), 0x91);
+        assert_eq!(Command::write(Register::WP.addr()).byte(), 0x8E);
+        // The read bit is cleared for a write even if the address had it
+        assert_eq!(Command::write(0x8F).byte(), 0x8E);
+        assert_eq!(Command::burst_read().byte(), 0xBF);
+        assert_eq!(Command::burst_write().byte(), 0xBE);
+        assert_eq!(Command::ram_burst_read().byte(), 0xFF);
+        assert_eq!(u8::from(Command::ram_burst_write()), 0xFE);
+        assert!(Command::burst_read().is_read());
+        assert!(!Command::ram_burst_write().is_read());
+        assert!(Command::burst_read().is_burst());
+        assert!(Command::ram_burst_write().is_burst());
+        assert!(!Command::write(Register::RAM.addr()).is_burst());
+        assert!(!Command::read(Register::TCS.addr()).is_burst());
+    }
+
+    #[test]
+    fn trickle_charger_descriptions_of_the_datasheet_patterns() {
+        assert_eq!(tc_describe(0xA5), "enabled, one diode, 2k");
+        assert_eq!(tc_describe(0xA6), "enabled, one diode, 4k");
+        assert_eq!(tc_describe(0xA7), "enabled, one diode, 8k");
+        assert_eq!(tc_describe(0xA9), "enabled, two diodes, 2k");
+        assert_eq!(tc_describe(0xAA), "enabled, two diodes, 4k");
+        assert_eq!(tc_describe(0xAB), "enabled, two diodes, 8k");
+        assert_eq!(tc_describe(0x5C), "disabled (power-on value)");
+        // No diode or no resistor selected, or a wrong pattern in the upper nibble
+        for byte in [0xA0, 0xA4, 0xA8, 0xAC, 0xAF, 0x00, 0xFF, 0x55] {
+            assert_eq!(tc_describe(byte), "disabled", "{byte:#04x}");
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! [`Delay`] on the host clock, enabled by the `std` feature
+
+use crate::Delay;
+use core::convert::Infallible;
+use std::time::{Duration, Instant};
+
+/// [`Delay`] implementation on `std::time::Instant`, to run the driver on a PC
+/// (e.g. with a mocked SPI bus)
+///
+/// Any `TIMER_HZ` can be used, `now` counts ticks of that frequency since the delay was created.
+pub struct StdDelay {
+    origin: Instant,
+    deadline: Option<Instant>,
+}
+
+impl StdDelay {
+    /// Create the delay, no countdown is running
+    pub fn new() -> Self {
+        StdDelay {
+            origin: Instant::now(),
+            deadline: None,
+        }
+    }
+}
+
+impl Default for StdDelay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const TIMER_HZ: u32> Delay<TIMER_HZ> for StdDelay {
+    type Error = Infallible;
+
+    fn now(&mut self) -> fugit::TimerInstantU32<TIMER_HZ> {
+        let ticks = self.origin.elapsed().as_nanos() * u128::from(TIMER_HZ) / 1_000_000_000;
+        // Wraps around like a hardware counter
+        fugit::TimerInstantU32::from_ticks(ticks as u32)
+    }
+
+    fn start(&mut self, duration: fugit::TimerDurationU32<TIMER_HZ>) -> Result<(), Self::Error> {
+        let nanos = u64::from(duration.ticks()) * 1_000_000_000 / u64::from(TIMER_HZ);
+        self.deadline = Some(Instant::now() + Duration::from_nanos(nanos));
+        Ok(())
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        match self.deadline {
+            Some(deadline) if Instant::now() < deadline => Err(nb::Error::WouldBlock),
+            _ => Ok(()),
+        }
+    }
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        self.deadline = None;
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+//! [`embedded-storage`](https://docs.rs/embedded-storage) over the RAM, enabled by the
+//! `embedded-storage` feature
+
+use crate::{Delay, DriverError, Ds1302Error, Interface, DS1302, RAM_SIZE};
+use core::convert::TryFrom;
+use embedded_storage::{ReadStorage, Storage};
+
+/// The 31 bytes of the RAM as a byte-addressable storage, see [`DS1302::ram_storage`]
+///
+/// Accesses starting at offset 0 use a RAM burst, other offsets are accessed byte by byte.
+/// An access beyond the 31 bytes fails with `Ds1302Error::Parameter`.
+pub struct RamStorage<'a, IF, CLK, const TIMER_HZ: u32>
+where
+    CLK: Delay<TIMER_HZ>,
+{
+    rtc: &'a mut DS1302<IF, CLK, TIMER_HZ>,
+}
+
+impl<IF, CLK, const TIMER_HZ: u32> DS1302<IF, CLK, TIMER_HZ>
+where
+    IF: Interface,
+    CLK: Delay<TIMER_HZ>,
+{
+    /// Borrow the RAM as an `embedded-storage` [`Storage`]
+    pub fn ram_storage(&mut self) -> RamStorage<'_, IF, CLK, TIMER_HZ> {
+        RamStorage { rtc: self }
+    }
+}
+
+// Index of the first byte, if `len` bytes from `offset` fit into the RAM
+fn ram_start(offset: u32, len: usize) -> Option<u8> {
+    let start = u8::try_from(offset).ok()?;
+    if usize::from(start) + len > RAM_SIZE {
+        return None;
+    }
+    Some(start)
+}
+
+impl<IF, CLK, const TIMER_HZ: u32> ReadStorage for RamStorage<'_, IF, CLK, TIMER_HZ>
+where
+    IF: Interface,
+    CLK: Delay<TIMER_HZ>,
+{
+    type Error = DriverError<IF, CLK, TIMER_HZ>;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        match ram_start(offset, bytes.len()) {
+            Some(0) => self.rtc.read_ram_burst(bytes),
+            Some(start) => self.rtc.read_ram_range(start, bytes),
+            None => Err(Ds1302Error::Parameter),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        RAM_SIZE
+    }
+}
+
+impl<IF, CLK, const TIMER_HZ: u32> Storage for RamStorage<'_, IF, CLK, TIMER_HZ>
+where
+    IF: Interface,
+    CLK: Delay<TIMER_HZ>,
+{
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        match ram_start(offset, bytes.len()) {
+            Some(0) => self.rtc.write_ram_burst(bytes).map(|_| ()),
+            Some(start) => self.rtc.write_ram_range(start, bytes),
+            None => Err(Ds1302Error::Parameter),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{fake_rtc, memory};
+
+    #[test]
+    fn reads_and_writes_through_the_storage_traits() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        let mut storage = rtc.ram_storage();
+        assert_eq!(storage.capacity(), 31);
+
+        storage.write(0, &[1, 2, 3]).unwrap();
+        storage.write(28, &[0xAA, 0xBB, 0xCC]).unwrap();
+        assert_eq!(memory.borrow()[32..35], [1, 2, 3]);
+        assert_eq!(memory.borrow()[60..63], [0xAA, 0xBB, 0xCC]);
+
+        let mut bytes = [0_u8; 4];
+        storage.read(0, &mut bytes).unwrap();
+        assert_eq!(bytes, [1, 2, 3, 0]);
+        storage.read(27, &mut bytes).unwrap();
+        assert_eq!(bytes, [0, 0xAA, 0xBB, 0xCC]);
+    }
+
+    #[test]
+    fn accesses_beyond_the_ram_are_rejected() {
+        let memory = memory();
+        let mut rtc = fake_rtc(&memory);
+        let mut storage = rtc.ram_storage();
+        let mut bytes = [0_u8; 4];
+        assert!(matches!(
+            storage.read(28, &mut bytes),
+            Err(Ds1302Error::Parameter)
+        ));
+        assert!(matches!(
+            storage.read(256, &mut bytes[..1]),
+            Err(Ds1302Error::Parameter)
+        ));
+        assert!(matches!(
+            storage.write(0, &[0; 32]),
+            Err(Ds1302Error::Parameter)
+        ));
+        assert!(matches!(
+            storage.write(31, &[0]),
+            Err(Ds1302Error::Parameter)
+        ));
+        assert_eq!(*memory.borrow(), [0; 64]);
+    }
+}
@@ -0,0 +1,177 @@
+//! [`time`](https://docs.rs/time) interop, enabled by the `time` feature
+//!
+//! [`Calendar`] converts to and from [`Date`], [`Clock`] to and from [`Time`].
+//! [`DateTime`] converts to and from [`PrimitiveDateTime`], [`to_primitive_datetime`] and
+//! [`from_primitive_datetime`] do the same for a separate clock and calendar.
+//!
+//! The chip stores the year as two BCD digits, so only the years 2000..=2099 can be converted
+//! to the DS1302 format. The day of the week is a plain 1..=7 counter on the chip; the conversions
+//! use the ISO numbering, Monday is 1 and Sunday is 7.
+
+use crate::{Calendar, Clock, DateTime, Ds1302Error, Hours};
+use ::time::{Date, PrimitiveDateTime, Time};
+use core::convert::TryFrom;
+
+impl TryFrom<Calendar> for Date {
+    type Error = Ds1302Error;
+
+    /// Fails if the calendar is not a valid date. The `day` field is ignored.
+    fn try_from(calendar: Calendar) -> Result<Self, Self::Error> {
+        let month = ::time::Month::try_from(calendar.month).map_err(|_| Ds1302Error::Parameter)?;
+        Date::from_calendar_date(i32::from(calendar.year), month, calendar.date)
+            .map_err(|_| Ds1302Error::Parameter)
+    }
+}
+
+impl TryFrom<Date> for Calendar {
+    type Error = Ds1302Error;
+
+    /// Fails if the year is outside of 2000..=2099. `day` is set to the ISO weekday of the date.
+    fn try_from(date: Date) -> Result<Self, Self::Error> {
+        if !(2000..=2099).contains(&date.year()) {
+            return Err(Ds1302Error::Parameter);
+        }
+        Ok(Calendar {
+            day: date.weekday().number_from_monday(),
+            date: date.day(),
+            month: u8::from(date.month()),
+            year: date.year() as u16,
+        })
+    }
+}
+
+impl TryFrom<Clock> for Time {
+    type Error = Ds1302Error;
+
+    /// 12-hour clocks are converted to 24-hour first. Fails if the clock is not a valid time.
+    fn try_from(clock: Clock) -> Result<Self, Self::Error> {
+        if !clock.hours.is_valid() {
+            return Err(Ds1302Error::Parameter);
+        }
+        Time::from_hms(clock.hours.hour24(), clock.minutes, clock.seconds)
+            .map_err(|_| Ds1302Error::Parameter)
+    }
+}
+
+impl From<Time> for Clock {
+    /// The clock is in 24-hour format, fractions of a second are dropped
+    fn from(time: Time) -> Self {
+        Clock {
+            hours: Hours::Hour24(time.hour()),
+            minutes: time.minute(),
+            seconds: time.second(),
+        }
+    }
+}
+
+/// Combine a clock and a calendar read from the chip into a [`PrimitiveDateTime`]
+///
+/// Fails if either of them is not valid, see [`Date::try_from`] and [`Time::try_from`].
+pub fn to_primitive_datetime(
+    clock: Clock,
+    calendar: Calendar,
+) -> Result<PrimitiveDateTime, Ds1302Error> {
+    let date = Date::try_from(calendar)?;
+    let time = Time::try_from(clock)?;
+    Ok(PrimitiveDateTime::new(date, time))
+}
+
+/// Split a [`PrimitiveDateTime`] into a 24-hour clock and a calendar that can be written to the chip
+///
+/// Fails if the year is outside of 2000..=2099.
+pub fn from_primitive_datetime(
+    datetime: PrimitiveDateTime,
+) -> Result<(Clock, Calendar), Ds1302Error> {
+    let calendar = Calendar::try_from(datetime.date())?;
+    Ok((Clock::from(datetime.time()), calendar))
+}
+
+impl TryFrom<DateTime> for PrimitiveDateTime {
+    type Error = Ds1302Error;
+
+    fn try_from(datetime: DateTime) -> Result<Self, Self::Error> {
+        to_primitive_datetime(datetime.clock, datetime.calendar)
+    }
+}
+
+impl TryFrom<PrimitiveDateTime> for DateTime {
+    type Error = Ds1302Error;
+
+    fn try_from(datetime: PrimitiveDateTime) -> Result<Self, Self::Error> {
+        let (clock, calendar) = from_primitive_datetime(datetime)?;
+        Ok(DateTime { clock, calendar })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn primitive(y: i32, mo: u8, d: u8, h: u8, mi: u8, s: u8) -> PrimitiveDateTime {
+        let month = ::time::Month::try_from(mo).unwrap();
+        Date::from_calendar_date(y, month, d)
+            .unwrap()
+            .with_hms(h, mi, s)
+            .unwrap()
+    }
+
+    #[test]
+    fn datetimes_round_trip() {
+        for datetime in [
+            primitive(2000, 1, 1, 0, 0, 0),
+            primitive(2024, 2, 29, 12, 30, 45),
+            primitive(2021, 11, 19, 19, 24, 0),
+            primitive(2099, 12, 31, 23, 59, 59),
+        ] {
+            let (clock, calendar) = from_primitive_datetime(datetime).unwrap();
+            assert_eq!(to_primitive_datetime(clock, calendar).unwrap(), datetime);
+            let converted = DateTime::try_from(datetime).unwrap();
+            assert_eq!(PrimitiveDateTime::try_from(converted).unwrap(), datetime);
+        }
+    }
+
+    #[test]
+    fn leap_day_has_its_weekday() {
+        let (_, calendar) = from_primitive_datetime(primitive(2024, 2, 29, 0, 0, 0)).unwrap();
+        // Thursday
+        assert_eq!((calendar.day, calendar.date, calendar.month), (4, 29, 2));
+        let not_leap = Calendar {
+            day: 4,
+            date: 29,
+            month: 2,
+            year: 2023,
+        };
+        assert!(matches!(
+            Date::try_from(not_leap),
+            Err(Ds1302Error::Parameter)
+        ));
+    }
+
+    #[test]
+    fn twelve_hour_noon_and_midnight_convert_to_24_hours() {
+        let calendar = Calendar::new(5, 19, 11, 2021).unwrap();
+        let noon = Clock::new(Hours::Hour12pm(12), 0, 0).unwrap();
+        let midnight = Clock::new(Hours::Hour12am(12), 0, 0).unwrap();
+        let pm = Clock::new(Hours::Hour12pm(7), 24, 0).unwrap();
+        assert_eq!(
+            to_primitive_datetime(noon, calendar).unwrap(),
+            primitive(2021, 11, 19, 12, 0, 0)
+        );
+        assert_eq!(
+            to_primitive_datetime(midnight, calendar).unwrap(),
+            primitive(2021, 11, 19, 0, 0, 0)
+        );
+        assert_eq!(
+            to_primitive_datetime(pm, calendar).unwrap(),
+            primitive(2021, 11, 19, 19, 24, 0)
+        );
+    }
+
+    #[test]
+    fn years_outside_the_chip_range_are_rejected() {
+        for year in [1999, 2100] {
+            let result = from_primitive_datetime(primitive(year, 6, 1, 0, 0, 0));
+            assert!(matches!(result, Err(Ds1302Error::Parameter)));
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! [`ufmt`](https://docs.rs/ufmt) formatting, enabled by the `ufmt` feature
+//!
+//! The output is the same as the `Display` implementations: `HH:MM:SS` for a [`Clock`]
+//! (12-hour clocks end with ` AM` or ` PM`) and `YYYY-MM-DD` for a [`Calendar`].
+
+use crate::{Calendar, Clock};
+use ::ufmt::{uDisplay, uWrite, Formatter};
+
+// Write `value` in decimal, padded with zeros to `width` digits
+fn write_padded<W>(f: &mut Formatter<'_, W>, value: u16, width: usize) -> Result<(), W::Error>
+where
+    W: uWrite + ?Sized,
+{
+    let mut digits = [b'0'; 5];
+    let mut rest = value;
+    let mut len = 0;
+    while rest > 0 || len < width {
+        digits[digits.len() - 1 - len] = b'0' + (rest % 10) as u8;
+        rest /= 10;
+        len += 1;
+    }
+    let start = digits.len() - len;
+    for &digit in &digits[start..] {
+        f.write_char(char::from(digit))?;
+    }
+    Ok(())
+}
+
+impl uDisplay for Clock {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        let (hours, suffix) = self.hours.display_parts();
+        write_padded(f, u16::from(hours), 2)?;
+        f.write_char(':')?;
+        write_padded(f, u16::from(self.minutes), 2)?;
+        f.write_char(':')?;
+        write_padded(f, u16::from(self.seconds), 2)?;
+        f.write_str(suffix)
+    }
+}
+
+impl uDisplay for Calendar {
+    fn fmt<W>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error>
+    where
+        W: uWrite + ?Sized,
+    {
+        write_padded(f, self.year, 4)?;
+        f.write_char('-')?;
+        write_padded(f, u16::from(self.month), 2)?;
+        f.write_char('-')?;
+        write_padded(f, u16::from(self.date), 2)
+    }
+}
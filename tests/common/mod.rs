@@ -0,0 +1,67 @@
+//! Fixtures shared by the integration tests
+#![allow(dead_code)]
+
+use core::convert::Infallible;
+use ds1302::{Delay, Mode, SpiInterface, DS1302};
+use embedded_hal_mock::eh0::digital::{Mock as PinMock, State, Transaction as PinTransaction};
+use embedded_hal_mock::eh0::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+
+pub const TIMER_HZ: u32 = 1_000_000;
+
+/// Timer whose countdowns are always over
+pub struct NoWait;
+
+impl Delay<TIMER_HZ> for NoWait {
+    type Error = Infallible;
+
+    fn now(&mut self) -> fugit::TimerInstantU32<TIMER_HZ> {
+        fugit::TimerInstantU32::from_ticks(0)
+    }
+
+    fn start(&mut self, _duration: fugit::TimerDurationU32<TIMER_HZ>) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Single register read of the write address `addr`, answering `value`
+pub fn read(addr: u8, value: u8) -> SpiTransaction {
+    SpiTransaction::transfer(vec![addr | 1, 0], vec![0, value])
+}
+
+/// Single register write of `value` at `addr`
+pub fn write(addr: u8, value: u8) -> SpiTransaction {
+    SpiTransaction::write(vec![addr, value])
+}
+
+/// CE high and low around each of `count` SPI transactions
+pub fn ce_pulses(count: usize) -> Vec<PinTransaction> {
+    (0..count)
+        .flat_map(|_| {
+            [
+                PinTransaction::set(State::High),
+                PinTransaction::set(State::Low),
+            ]
+        })
+        .collect()
+}
+
+/// Driver after `new` found a running clock in 24-hour format, expecting `spi` afterwards.
+/// CE goes high and low around every SPI transaction.
+pub fn rtc(
+    spi: &[SpiTransaction],
+) -> (
+    DS1302<SpiInterface<SpiMock, PinMock>, NoWait, TIMER_HZ>,
+    SpiMock,
+    PinMock,
+) {
+    let mut expected = vec![read(0x80, 0x00), read(0x84, 0x12)];
+    expected.extend_from_slice(spi);
+    let spi = SpiMock::new(&expected);
+    let cs = PinMock::new(&ce_pulses(expected.len()));
+    let rtc = DS1302::new(spi.clone(), cs.clone(), Mode::Hour24, NoWait).unwrap();
+    (rtc, spi, cs)
+}
@@ -0,0 +1,52 @@
+//! Transactions traced through the `log` crate, enabled by the `log` feature
+
+mod common;
+
+use common::{read, NoWait};
+use ds1302::{Mode, DS1302};
+use embedded_hal_mock::eh0::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::sync::Mutex;
+
+/// Logger keeping the messages of the driver
+struct Capture(Mutex<Vec<String>>);
+
+impl Log for Capture {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        if record.target().starts_with("ds1302") {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: Capture = Capture(Mutex::new(Vec::new()));
+
+#[test]
+fn get_clock_logs_its_command_bytes() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let mut spi = SpiMock::new(&[
+        read(0x80, 0x00),
+        read(0x84, 0x12),
+        SpiTransaction::transfer(vec![0xBF, 0, 0, 0], vec![0, 0x56, 0x34, 0x12]),
+    ]);
+    let mut rtc = DS1302::new_no_cs(spi.clone(), Mode::Hour24, NoWait).unwrap();
+    rtc.get_clock().unwrap();
+    spi.done();
+
+    assert_eq!(
+        *LOGGER.0.lock().unwrap(),
+        [
+            "ds1302 read 0x81: [00]",
+            "ds1302 read 0x85: [12]",
+            "ds1302 read 0xbf: [56, 34, 12]",
+        ]
+    );
+}
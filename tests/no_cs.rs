@@ -0,0 +1,38 @@
+//! The driver can be created without a chip select pin, the bus drives CE
+
+mod common;
+
+use common::{read, TIMER_HZ};
+use ds1302::{Mode, NoDelay, SpiNoCsInterface, DS1302};
+use embedded_hal_mock::eh0::spi::Mock as SpiMock;
+
+#[test]
+fn new_no_cs_takes_only_the_bus() {
+    let mut spi = SpiMock::new(&[read(0x80, 0x00), read(0x84, 0x12), read(0x82, 0x34)]);
+    let mut rtc: DS1302<SpiNoCsInterface<SpiMock>, NoDelay, TIMER_HZ> =
+        DS1302::new_no_cs(spi.clone(), Mode::Hour24, NoDelay).unwrap();
+    assert_eq!(rtc.get_minutes().unwrap(), 34);
+    let (_spi, NoDelay) = rtc.destroy();
+    spi.done();
+}
+
+#[cfg(feature = "eh1")]
+#[test]
+fn new_spi_device_takes_only_the_device() {
+    use ds1302::SpiDeviceInterface;
+    use embedded_hal_mock::eh1::spi::{Mock, Transaction};
+
+    let read = |addr: u8, value: u8| {
+        [
+            Transaction::transaction_start(),
+            Transaction::transfer_in_place(vec![addr | 1, 0], vec![0, value]),
+            Transaction::transaction_end(),
+        ]
+    };
+    let expected: Vec<_> = [read(0x80, 0x00), read(0x84, 0x12)].concat();
+    let mut spi = Mock::new(&expected);
+    let rtc: DS1302<SpiDeviceInterface<Mock<u8>>, NoDelay, TIMER_HZ> =
+        DS1302::new_spi_device(spi.clone(), Mode::Hour24, NoDelay).unwrap();
+    rtc.destroy();
+    spi.done();
+}
@@ -0,0 +1,27 @@
+//! `NoDelay` can be passed where the examples used to implement a dummy `Delay`
+
+mod common;
+
+use common::{ce_pulses, read};
+use ds1302::{Mode, NoDelay, DS1302};
+use embedded_hal_mock::eh0::digital::Mock as PinMock;
+use embedded_hal_mock::eh0::spi::Mock as SpiMock;
+
+#[test]
+fn new_accepts_no_delay() {
+    let mut spi = SpiMock::new(&[read(0x80, 0x00), read(0x84, 0x12), read(0x80, 0x42)]);
+    let mut cs = PinMock::new(&ce_pulses(3));
+    let mut rtc: DS1302<_, NoDelay, 1_000_000> =
+        DS1302::new(spi.clone(), cs.clone(), Mode::Hour24, NoDelay).unwrap();
+    assert_eq!(rtc.get_seconds().unwrap(), 42);
+    spi.done();
+    cs.done();
+}
+
+#[test]
+fn no_delay_works_at_any_timer_frequency() {
+    let mut spi = SpiMock::new(&[read(0x80, 0x00), read(0x84, 0x12)]);
+    let _rtc: DS1302<_, NoDelay, 32_768> =
+        DS1302::new_no_cs(spi.clone(), Mode::Hour24, NoDelay).unwrap();
+    spi.done();
+}
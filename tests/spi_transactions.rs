@@ -0,0 +1,65 @@
+//! Exact SPI transactions of the blocking driver on an `embedded-hal` 0.2 bus with a CE pin
+
+mod common;
+
+use common::{read, rtc, write};
+use ds1302::{Calendar, Clock, Ds, Hours, Rs};
+use embedded_hal_mock::eh0::spi::Transaction as SpiTransaction;
+
+#[test]
+fn get_clock_reads_a_clock_burst() {
+    let (mut rtc, mut spi, mut cs) = rtc(&[SpiTransaction::transfer(
+        vec![0xBF, 0, 0, 0],
+        vec![0, 0x56, 0x34, 0x12],
+    )]);
+    let clock = rtc.get_clock().unwrap();
+    assert_eq!(clock.seconds, 56);
+    assert_eq!(clock.minutes, 34);
+    assert_eq!(clock.hours, Hours::Hour24(12));
+    spi.done();
+    cs.done();
+}
+
+#[test]
+fn set_clock_calendar_clears_write_protection_then_bursts() {
+    let (mut rtc, mut spi, mut cs) = rtc(&[
+        read(0x8E, 0x80),
+        write(0x8E, 0x00),
+        SpiTransaction::write(vec![0xBE, 0x56, 0x34, 0x12, 0x31, 0x12, 0x04, 0x20, 0x00]),
+    ]);
+    let clock = Clock::new(Hours::Hour24(12), 34, 56).unwrap();
+    let calendar = Calendar::new(4, 31, 12, 2020).unwrap();
+    rtc.set_clock_calendar(clock, calendar).unwrap();
+    spi.done();
+    cs.done();
+}
+
+#[test]
+fn trickle_charger_enable_and_disable() {
+    let (mut rtc, mut spi, mut cs) = rtc(&[
+        read(0x8E, 0x80),
+        write(0x8E, 0x00),
+        write(0x90, 0xA5),
+        write(0x90, 0x5C),
+    ]);
+    rtc.tc_enable(Ds::ONE07V, Rs::R2K).unwrap();
+    // The protection is known to be cleared, the second write goes straight out
+    rtc.tc_disable().unwrap();
+    spi.done();
+    cs.done();
+}
+
+#[test]
+fn ram_burst_write_and_read() {
+    let (mut rtc, mut spi, mut cs) = rtc(&[
+        read(0x8E, 0x00),
+        SpiTransaction::write(vec![0xFE, 1, 2, 3]),
+        SpiTransaction::transfer(vec![0xFF, 0, 0, 0], vec![0, 1, 2, 3]),
+    ]);
+    assert_eq!(rtc.write_ram_burst(&[1, 2, 3]).unwrap(), 3);
+    let mut buf = [0_u8; 3];
+    rtc.read_ram_burst(&mut buf).unwrap();
+    assert_eq!(buf, [1, 2, 3]);
+    spi.done();
+    cs.done();
+}
@@ -0,0 +1,49 @@
+//! Clock and calendar round trip on the host with `StdDelay` and a mocked SPI bus
+
+mod common;
+
+use common::{ce_pulses, read, TIMER_HZ};
+use ds1302::{Calendar, Clock, Hours, Mode, StdDelay, DS1302};
+use embedded_hal_mock::eh0::digital::Mock as PinMock;
+use embedded_hal_mock::eh0::spi::{Mock as SpiMock, Transaction as SpiTransaction};
+use fugit::ExtU32;
+use std::time::{Duration, Instant};
+
+#[test]
+fn clock_calendar_round_trip() {
+    // 2021-11-19 19:24:00, a Friday
+    let regs = [0x00, 0x24, 0x19, 0x19, 0x11, 0x05, 0x21];
+    let mut burst_write = vec![0xBE];
+    burst_write.extend_from_slice(&regs);
+    burst_write.push(0x00);
+    let mut burst_read = vec![0];
+    burst_read.extend_from_slice(&regs);
+    let expected = [
+        read(0x80, 0x00),
+        read(0x84, 0x12),
+        read(0x8E, 0x00),
+        SpiTransaction::write(burst_write),
+        SpiTransaction::transfer(vec![0xBF, 0, 0, 0, 0, 0, 0, 0], burst_read),
+    ];
+    let mut spi = SpiMock::new(&expected);
+    let mut cs = PinMock::new(&ce_pulses(expected.len()));
+
+    let start = Instant::now();
+    let mut rtc: DS1302<_, StdDelay, TIMER_HZ> = DS1302::new_with_timing(
+        spi.clone(),
+        cs.clone(),
+        Mode::Hour24,
+        StdDelay::new(),
+        1.millis(),
+    )
+    .unwrap();
+    let clock = Clock::new(Hours::Hour24(19), 24, 0).unwrap();
+    let calendar = Calendar::new(5, 19, 11, 2021).unwrap();
+    rtc.set_clock_calendar(clock, calendar).unwrap();
+    assert_eq!(rtc.get_clock_calendar().unwrap(), (clock, calendar));
+    // The CE inactive time was waited between the 5 transactions
+    assert!(start.elapsed() >= Duration::from_millis(4));
+
+    spi.done();
+    cs.done();
+}